@@ -3,8 +3,7 @@ use chrono::Local;
 
 fn main() -> Result<(), Box<dyn Error>> {
 	// Provide git commit hash
-	let commit_hash: String;
-	if cfg!(debug_assertions) {
+	let commit_hash: String = if cfg!(debug_assertions) {
 		println!("cargo:rustc-rerun-if-changed=.git/HEAD");
 		let git_head = fs::read_to_string(".git/HEAD")?;
 		if git_head.starts_with("ref:") {
@@ -14,14 +13,14 @@ fn main() -> Result<(), Box<dyn Error>> {
 				}
 			}
 		}
-		let cmd = Command::new("git").args(&["rev-parse", "--short", "HEAD"]).output()?;	
-		commit_hash = String::from_utf8(cmd.stdout)?;
+		let cmd = Command::new("git").args(["rev-parse", "--short", "HEAD"]).output()?;
+		String::from_utf8(cmd.stdout)?
 	} else {
 		const GIT_HASH_PATH: &str = "installer/git-hash";
 		println!("cargo:rustc-rerun-if-changed={}", GIT_HASH_PATH);
 		// Use installer/git-hash to get commit hash in release builds
-		commit_hash = fs::read_to_string(GIT_HASH_PATH)?;
-	}
+		fs::read_to_string(GIT_HASH_PATH)?
+	};
 	println!("cargo:rustc-env=COMMIT_HASH={}", commit_hash);
 
 	// Provide build date
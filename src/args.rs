@@ -1,31 +1,51 @@
 use std::process::exit;
 use std::env;
 
+/// What my_timers should do
+#[derive(PartialEq)]
+pub enum Command {
+	Run, // Run events (default)
+	List, // Print each event's schedule and exit
+	Check // Validate config.json and events.conf without connecting to the database, and exit
+}
+
 /// Program arguments gathered from CLI args and/or env variables
 pub struct Args {
+	pub command: Command,
+	pub check_sql: bool, // Whether check also syntax-checks SQL statements
 	pub verbose: bool,
 	pub config_path: String,
-	pub events_path: String
+	pub events_path: String,
+	pub state_path: String
 }
 
 const CONFIG_PATH_ENV: &str = "MY_TIMERS_CONFIG";
 const CONFIG_PATH_DEFAULT: &str = "config.json";
 const EVENTS_PATH_ENV: &str = "MY_TIMERS_EVENTS";
 const EVENTS_PATH_DEFAULT: &str = "events.conf";
+const STATE_PATH_ENV: &str = "MY_TIMERS_STATE";
+const STATE_PATH_DEFAULT: &str = "state.json";
 
 /// Parse program arguments
 pub fn args() -> Args {
 	// Argument values to be set
+	let mut command = Command::Run;
+	let mut check_sql = false;
 	let mut verbose = false;
 	let mut config_path: Option<String> = None;
 	let mut events_path: Option<String> = None;
+	let mut state_path: Option<String> = None;
 
 	let args: Vec<String> = env::args().collect();
 	for (i, arg) in args.iter().enumerate() {
 		match arg.as_str() {
 			"-h" | "--help" => {
-				const USAGE: &str = "my_timers [-c/--config /path/to/config.json] [-e/--events /path/to/events.conf] [-v/--verbose] [-h/--help] [-V/--version]";
-				println!("{}", "Usage:".to_string() + "\n\t" + USAGE);
+				const USAGE: &str = "my_timers [list|check [--sql]] [-c/--config /path/to/config.json] [-e/--events /path/to/events.conf|events.d] [-s/--state /path/to/state.json] [-v/--verbose] [-h/--help] [-V/--version]";
+				const COMMANDS: &str = "list\tPrint each event's schedule and upcoming runs, without connecting to the database\n\t\
+					check\tValidate config.json and events.conf without connecting to the database,\n\t\t\
+					syntax-checking SQL statements for the configured driver with --sql.\n\t\t\
+					Exits with 0 if both are valid, 1 if events.conf is invalid, or 2 if config.json or a file can't be read";
+				println!("{}", "Usage:".to_string() + "\n\t" + USAGE + "\nCommands:\n\t" + COMMANDS);
 				exit(0);
 			},
 			"-V" | "--version" => {
@@ -35,6 +55,15 @@ pub fn args() -> Args {
 				println!("my_timers v{}\n\tbuilt {} from commit {}\n\tCopyright (c) 2022-2023 Keith Scroggs <very-amused>", VERSION, BUILD_DATE, COMMIT_HASH);
 				exit(0);
 			},
+			"list" if i == 1 => {
+				command = Command::List;
+			},
+			"check" if i == 1 => {
+				command = Command::Check;
+			},
+			"--sql" => {
+				check_sql = true;
+			},
 			"-v" | "--verbose" => {
 				verbose = true;
 			},
@@ -43,29 +72,47 @@ pub fn args() -> Args {
 			},
 			"-e" | "--events" if i < args.len() - 1 => {
 				events_path = Some(args[i+1].to_string());
+			},
+			"-s" | "--state" if i < args.len() - 1 => {
+				state_path = Some(args[i+1].to_string());
 			}
 			_ => {}
 		}
 	}
+	// --sql only changes what check validates, so it's an error anywhere else
+	if check_sql && command != Command::Check {
+		eprintln!("--sql can only be used with check, see my_timers --help");
+		exit(1);
+	}
 	// Set unspecified flags from env vars
-	if config_path == None {
+	if config_path.is_none() {
 		if let Ok(path) = env::var(CONFIG_PATH_ENV) {
 			config_path = Some(path);
 		} else if verbose {
 			eprintln!("{} is not set, defaulting to {}", CONFIG_PATH_ENV, CONFIG_PATH_DEFAULT);
 		}
 	}
-	if events_path == None {
+	if events_path.is_none() {
 		if let Ok(path) = env::var(EVENTS_PATH_ENV) {
 			events_path = Some(path);
 		} else if verbose {
 			eprintln!("{} is not set, defaulting to {}", EVENTS_PATH_ENV, EVENTS_PATH_DEFAULT);
 		}
 	}
+	if state_path.is_none() {
+		if let Ok(path) = env::var(STATE_PATH_ENV) {
+			state_path = Some(path);
+		} else if verbose {
+			eprintln!("{} is not set, defaulting to {}", STATE_PATH_ENV, STATE_PATH_DEFAULT);
+		}
+	}
 
 	Args {
+		command,
+		check_sql,
 		verbose,
 		config_path: if let Some(path) = config_path { path } else { CONFIG_PATH_DEFAULT.to_string() },
-		events_path: if let Some(path) = events_path { path } else { EVENTS_PATH_DEFAULT.to_string() }
+		events_path: if let Some(path) = events_path { path } else { EVENTS_PATH_DEFAULT.to_string() },
+		state_path: if let Some(path) = state_path { path } else { STATE_PATH_DEFAULT.to_string() }
 	}
 }
@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use serde::Deserialize;
+
+use crate::cron::{error::CronParseError, CronInterval};
+use crate::events::Event;
+use self::error::BlackoutError;
+
+mod calendar;
+pub mod error;
+#[cfg(test)]
+mod tests;
+
+/// Config for a named set of times during which events don't run
+#[derive(Deserialize)]
+pub struct Config {
+	// Whether the blackout applies to every event, instead of only events tagged with @blackout(name)
+	#[serde(default)]
+	global: bool,
+	// Cron intervals matching the minutes during which runs are suppressed
+	#[serde(default)]
+	windows: Vec<String>,
+	// Dates (2026-12-24) or inclusive date ranges (2026-12-24/2026-12-26) in the host's local timezone
+	#[serde(default)]
+	dates: Vec<String>,
+	// iCalendar (.ics) or CSV file of dates
+	file: Option<String>
+}
+
+/// A period during which runs are suppressed
+enum Window {
+	Cron(CronInterval),
+	Period {
+		start: DateTime<Utc>,
+		end: DateTime<Utc>, // Exclusive
+		description: String
+	}
+}
+
+impl Window {
+	/// Description of the window if it contains `now`
+	fn check(&self, now: &DateTime<Utc>) -> Option<String> {
+		match self {
+			Self::Cron(interval) if interval.match_time(now) => Some(format!("window {}", interval)),
+			Self::Period { start, end, description } if start <= now && now < end => Some(description.clone()),
+			_ => None
+		}
+	}
+}
+
+struct Blackout {
+	name: String,
+	global: bool,
+	windows: Vec<Window>
+}
+
+/// Every configured blackout
+pub struct Blackouts(Vec<Blackout>);
+
+impl Blackouts {
+	/// Parse blackout windows and dates, and load calendar files
+	pub fn load(config: &BTreeMap<String, Config>) -> Result<Self, BlackoutError> {
+		let mut blackouts = Vec::with_capacity(config.len());
+		for (name, c) in config {
+			let mut windows = Vec::new();
+			for w in &c.windows {
+				let interval: CronInterval = w.parse().map_err(|e| BlackoutError::CronParseError(name.clone(), e))?;
+				// H values are derived from an event's label, which blackouts don't have
+				if interval.has_hashes() {
+					let e = CronParseError::SyntaxError(format!("{} - H can't be used in blackout windows", w), None);
+					return Err(BlackoutError::CronParseError(name.clone(), e));
+				}
+				windows.push(Window::Cron(interval));
+			}
+			for d in &c.dates {
+				let (start, end) = d.split_once('/').unwrap_or((d.as_str(), d.as_str()));
+				windows.push(Window::Period {
+					start: start_of_day(parse_date(start)?),
+					end: start_of_day(parse_date(end)?.succ_opt().unwrap()),
+					description: d.clone()
+				});
+			}
+			if let Some(path) = &c.file {
+				windows.append(&mut calendar::load(path)?);
+			}
+			blackouts.push(Blackout {
+				name: name.clone(),
+				global: c.global,
+				windows
+			});
+		}
+		Ok(Self(blackouts))
+	}
+
+	/// Names of the configured blackouts, which events can be tagged with
+	pub fn names(&self) -> Vec<&str> {
+		self.0.iter().map(|b| b.name.as_str()).collect()
+	}
+
+	/// The reason an event's run is suppressed at `now`, if any
+	pub fn check(&self, evt: &Event, now: &DateTime<Utc>) -> Option<String> {
+		self.0.iter()
+			.filter(|b| b.global || evt.blackouts.contains(&b.name))
+			.find_map(|b| b.windows.iter()
+				.find_map(|w| w.check(now))
+				.map(|reason| format!("{} ({})", b.name, reason)))
+	}
+}
+
+/// Parse a date (i.e 2026-12-24)
+fn parse_date(s: &str) -> Result<NaiveDate, BlackoutError> {
+	NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+		.map_err(|e| BlackoutError::InvalidDate(format!("{} ({})", s, e)))
+}
+
+/// First instant of a date in the host's local timezone
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+	let midnight = date.and_time(NaiveTime::MIN);
+	// Midnight can be skipped by a DST transition, in which case the day starts an hour later
+	Local.from_local_datetime(&midnight).earliest()
+		.or_else(|| Local.from_local_datetime(&(midnight + chrono::Duration::hours(1))).earliest())
+		.map(|t| t.with_timezone(&Utc))
+		.unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+}
@@ -0,0 +1,129 @@
+use std::{fs, path::Path};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use super::{error::BlackoutError, parse_date, start_of_day, Window};
+
+/// Load blackout periods from an iCalendar (.ics) or CSV file
+pub(super) fn load(path: &str) -> Result<Vec<Window>, BlackoutError> {
+	let contents = fs::read_to_string(path).map_err(|e| BlackoutError::FileError(path.into(), e))?;
+	let extension = Path::new(path).extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+	match extension.as_deref() {
+		Some("ics") => ical(&contents).map_err(|e| BlackoutError::CalendarError(path.into(), e)),
+		Some("csv") => csv(&contents).map_err(|e| BlackoutError::CalendarError(path.into(), e)),
+		_ => Err(BlackoutError::CalendarError(path.into(), "expected a .ics or .csv file".into()))
+	}
+}
+
+/// Parse CSV lines of `start date[,end date][,description]`, where the end date is inclusive.
+/// Blank lines, # comments and a header line are ignored.
+pub(super) fn csv(contents: &str) -> Result<Vec<Window>, String> {
+	let mut windows = Vec::new();
+	let mut header = true; // Whether the next line may be a header, which is the first line that isn't blank or a comment
+	for (i, line) in contents.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		let (start, mut rest) = line.split_once(',').unwrap_or((line, ""));
+		let start = match parse_date(start) {
+			Ok(start) => start,
+			Err(_) if header => {
+				header = false;
+				continue;
+			},
+			Err(e) => return Err(format!("line {}: {}", i + 1, e))
+		};
+		header = false;
+		// The end date is optional, in which case the second field starts the description
+		let mut end = start;
+		let (second, description) = rest.split_once(',').unwrap_or((rest, ""));
+		if let Ok(d) = parse_date(second) {
+			end = d;
+			rest = description;
+		}
+		// Descriptions are kept as written, including any commas
+		let description = rest.trim();
+		windows.push(Window::Period {
+			start: start_of_day(start),
+			end: start_of_day(end.succ_opt().unwrap()),
+			description: if description.is_empty() { line.into() } else { description.into() }
+		});
+	}
+	Ok(windows)
+}
+
+/// Parse the VEVENTs of an iCalendar file, using their DTSTART, DTEND and SUMMARY.
+/// Recurring events (RRULE) aren't expanded, only their first occurrence is used.
+pub(super) fn ical(contents: &str) -> Result<Vec<Window>, String> {
+	// Unfold lines, continuation lines start with a space or tab
+	let mut lines: Vec<String> = Vec::new();
+	for line in contents.lines() {
+		match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+			(Some(continued), Some(last)) => last.push_str(continued),
+			_ => lines.push(line.to_string())
+		}
+	}
+
+	let mut windows = Vec::new();
+	let (mut start, mut end, mut summary) = (None, None, None);
+	let mut in_event = false;
+	for line in &lines {
+		let Some((name, value)) = line.split_once(':') else {
+			continue;
+		};
+		let mut params = name.split(';');
+		let name = params.next().unwrap().to_ascii_uppercase();
+		let tzid = params.find_map(|p| p.strip_prefix("TZID="));
+		match (name.as_str(), value) {
+			("BEGIN", "VEVENT") => {
+				in_event = true;
+				(start, end, summary) = (None, None, None);
+			},
+			("END", "VEVENT") => {
+				in_event = false;
+				let Some((start, all_day)) = start else {
+					return Err(format!("event {} is missing DTSTART", summary.unwrap_or_default()));
+				};
+				let end = match end {
+					Some((end, _)) => end,
+					// All day events without an end last one day, others last no time at all
+					None if all_day => start + Duration::days(1),
+					None => start
+				};
+				windows.push(Window::Period {
+					start,
+					end,
+					description: summary.take().unwrap_or_else(|| "calendar event".into())
+				});
+			},
+			("DTSTART", _) if in_event => start = Some(ical_time(value, tzid)?),
+			("DTEND", _) if in_event => end = Some(ical_time(value, tzid)?),
+			("SUMMARY", _) if in_event => summary = Some(value.replace("\\,", ",").replace("\\;", ";")),
+			_ => {}
+		}
+	}
+	Ok(windows)
+}
+
+/// Parse an iCalendar DATE (20261224) or DATE-TIME (20261224T090000, 20261224T090000Z),
+/// returning the instant and whether it was a date
+fn ical_time(value: &str, tzid: Option<&str>) -> Result<(DateTime<Utc>, bool), String> {
+	if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+		return Ok((start_of_day(date), true));
+	}
+	let invalid = || format!("invalid date {}", value);
+	if let Some(utc) = value.strip_suffix('Z') {
+		let t = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+		return Ok((Utc.from_utc_datetime(&t), false));
+	}
+	let t = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+	let t = match tzid {
+		Some(tzid) => {
+			let tz: Tz = tzid.parse().map_err(|_| format!("unknown timezone {}", tzid))?;
+			tz.from_local_datetime(&t).earliest().map(|t| t.with_timezone(&Utc))
+		},
+		None => Local.from_local_datetime(&t).earliest().map(|t| t.with_timezone(&Utc))
+	};
+	t.map(|t| (t, false)).ok_or_else(invalid)
+}
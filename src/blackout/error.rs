@@ -0,0 +1,26 @@
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::cron::error::CronParseError;
+
+/// A blackout configuration error
+#[derive(Debug)]
+pub enum BlackoutError {
+	CronParseError(String, CronParseError), // (blackout, error)
+	InvalidDate(String),
+	FileError(String, std::io::Error), // (path, error)
+	CalendarError(String, String) // (path, error)
+}
+
+impl Display for BlackoutError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::CronParseError(name, e) => write!(f, "Invalid window in blackout {}: {}", name, e),
+			Self::InvalidDate(e) => write!(f, "Invalid blackout date: {}", e),
+			Self::FileError(path, e) => write!(f, "Failed to read {}: {}", path, e),
+			Self::CalendarError(path, e) => write!(f, "Invalid calendar {}: {}", path, e)
+		}
+	}
+}
+
+impl Error for BlackoutError {}
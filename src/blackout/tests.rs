@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use chrono::{NaiveDate, TimeZone, Utc};
+
+use super::{calendar, start_of_day, Blackouts, Config, Window};
+use crate::test_util::tree;
+
+/// (start, end, description) of each period window
+fn periods(windows: Vec<Window>) -> Vec<(String, String, String)> {
+	windows.into_iter().map(|w| match w {
+		Window::Period { start, end, description } => (start.to_rfc3339(), end.to_rfc3339(), description),
+		Window::Cron(interval) => panic!("unexpected window {}", interval)
+	}).collect()
+}
+
+/// Start of a day in the host's local timezone, as periods are parsed
+fn day(y: i32, m: u32, d: u32) -> String {
+	start_of_day(NaiveDate::from_ymd_opt(y, m, d).unwrap()).to_rfc3339()
+}
+
+#[test]
+fn csv() {
+	let contents = "# Company holidays\n\nstart,end,description\n2026-12-24,2026-12-26,Christmas\n2026-12-31\n 2027-01-01 , New Year's Day \n2027-02-01,Comma, in description\n";
+	assert_eq!(periods(calendar::csv(contents).unwrap()), vec![
+		(day(2026, 12, 24), day(2026, 12, 27), "Christmas".into()),
+		(day(2026, 12, 31), day(2027, 1, 1), "2026-12-31".into()),
+		(day(2027, 1, 1), day(2027, 1, 2), "New Year's Day".into()),
+		(day(2027, 2, 1), day(2027, 2, 2), "Comma, in description".into())
+	]);
+
+	// Only the first line that isn't blank or a comment can be a header
+	assert!(calendar::csv("2026-12-24\nstart,end\n").err().unwrap().starts_with("line 2:"));
+	assert!(calendar::csv("2026-12-24\n# comment\n24/12/2026\n").err().unwrap().starts_with("line 3:"));
+	assert!(calendar::csv("start\nend\n").is_err());
+	assert!(calendar::csv("").unwrap().is_empty());
+}
+
+#[test]
+fn ical() {
+	let contents = [
+		"BEGIN:VCALENDAR",
+		"BEGIN:VEVENT",
+		"DTSTART;VALUE=DATE:20261224",
+		"DTEND;VALUE=DATE:20261227",
+		"SUMMARY:Christmas\\, Boxing Day",
+		"END:VEVENT",
+		"BEGIN:VEVENT",
+		"DTSTART:20261231T220000Z",
+		"DTEND:20270101T020000Z",
+		"SUMMARY:New Year's",
+		"  Eve",
+		"END:VEVENT",
+		"BEGIN:VEVENT",
+		"DTSTART;TZID=Europe/Berlin:20270301T090000",
+		"DTEND;TZID=Europe/Berlin:20270301T170000",
+		"END:VEVENT",
+		"BEGIN:VEVENT",
+		"DTSTART;VALUE=DATE:20270401",
+		"RRULE:FREQ=YEARLY",
+		"SUMMARY:All day",
+		"END:VEVENT",
+		"END:VCALENDAR"
+	].join("\r\n");
+	assert_eq!(periods(calendar::ical(&contents).unwrap()), vec![
+		(day(2026, 12, 24), day(2026, 12, 27), "Christmas, Boxing Day".into()),
+		(
+			Utc.with_ymd_and_hms(2026, 12, 31, 22, 0, 0).unwrap().to_rfc3339(),
+			Utc.with_ymd_and_hms(2027, 1, 1, 2, 0, 0).unwrap().to_rfc3339(),
+			"New Year's Eve".into()
+		),
+		(
+			Utc.with_ymd_and_hms(2027, 3, 1, 8, 0, 0).unwrap().to_rfc3339(),
+			Utc.with_ymd_and_hms(2027, 3, 1, 16, 0, 0).unwrap().to_rfc3339(),
+			"calendar event".into()
+		),
+		// All day events without an end last one day, recurrences aren't expanded
+		(day(2027, 4, 1), day(2027, 4, 2), "All day".into())
+	]);
+
+	assert!(calendar::ical("BEGIN:VEVENT\nSUMMARY:No start\nEND:VEVENT").err().unwrap().contains("missing DTSTART"));
+	assert!(calendar::ical("BEGIN:VEVENT\nDTSTART:2026-12-24\nEND:VEVENT").err().unwrap().contains("invalid date"));
+	assert!(calendar::ical("BEGIN:VEVENT\nDTSTART;TZID=Mars/Olympus:20261224T090000\nEND:VEVENT").err().unwrap().contains("unknown timezone"));
+}
+
+/// Load blackouts from their config.json representation
+fn load(json: &str) -> Result<Blackouts, super::error::BlackoutError> {
+	let config: BTreeMap<String, Config> = serde_json::from_str(json).unwrap();
+	Blackouts::load(&config)
+}
+
+#[test]
+fn windows() {
+	assert!(load(r#"{"nightly": {"windows": ["* 0-6 * * *"], "dates": ["2026-12-24/2026-12-26"]}}"#).is_ok());
+
+	// H values are derived from event labels, so they can't be used in blackouts
+	for window in ["H * * * *", "H/15 * * * *", "0 H(0-6) * * *", "0,H 0 * * *", "H * * * * *"] {
+		let e = load(&format!(r#"{{"nightly": {{"windows": ["{}"]}}}}"#, window)).err().unwrap_or_else(|| panic!("{}", window));
+		assert!(e.to_string().contains("H can't be used"), "{}: {}", window, e);
+	}
+	assert!(load(r#"{"nightly": {"dates": ["2026-13-01"]}}"#).is_err());
+}
+
+#[tokio::test]
+async fn unknown_blackouts() {
+	let dir = tree("unknown_blackouts", &[("events.conf", "\
+First:
+0 * * * * @blackout(nightly) @blackout(weekend)
+	SELECT 1;
+
+Second:
+0 0 * * 8 @blackout(holidays)
+	SELECT 1;
+")]);
+	let path = dir.file("events.conf");
+	let blackouts = load(r#"{"nightly": {"windows": ["* 0-6 * * *"]}}"#).unwrap();
+	let e = crate::events::parse(&path, crate::events::Syntax::default(), crate::events::Validation::None, Some(&blackouts.names())).await.err().unwrap();
+
+	// Every unknown blackout is reported at its tag, along with the file's other errors
+	let expected = [
+		format!("error: Unknown blackout weekend (blackouts are configured in config.json)\n --> {}:2:40\n  |\n2 | 0 * * * * @blackout(nightly) @blackout(weekend)\n  |                                        ^^^^^^^", path),
+		format!("error: Invalid cron syntax: value 8 exceeds field range 0-7\n --> {}:6:9\n  |\n6 | 0 0 * * 8 @blackout(holidays)\n  |         ^", path),
+		format!("error: Unknown blackout holidays (blackouts are configured in config.json)\n --> {}:6:21\n  |\n6 | 0 0 * * 8 @blackout(holidays)\n  |                     ^^^^^^^^", path),
+		format!("error: could not parse {} due to 3 previous errors", path)
+	];
+	assert_eq!(e.to_string(), expected.join("\n\n"));
+
+	let blackouts = load(r#"{"nightly": {}, "weekend": {}, "holidays": {}}"#).unwrap();
+	let e = crate::events::parse(&path, crate::events::Syntax::default(), crate::events::Validation::None, Some(&blackouts.names())).await.err().unwrap();
+	assert!(!e.to_string().contains("Unknown blackout"), "{}", e);
+}
@@ -0,0 +1,63 @@
+use crate::{args, blackout, config, events};
+
+#[cfg(test)]
+mod tests;
+
+/// Exit codes of `my_timers check`
+pub const OK: i32 = 0;
+pub const INVALID_EVENTS: i32 = 1;
+pub const INVALID_CONFIG: i32 = 2;
+
+/// Validate config.json and events.conf without connecting to the database, returning the exit code
+pub async fn check(args: &args::Args) -> i32 {
+	let config = match config::parse(&args.config_path) {
+		Ok(config) => config,
+		Err(e) => {
+			eprintln!("Failed to parse {}: {}", &args.config_path, e);
+			return INVALID_CONFIG;
+		}
+	};
+	let blackouts = match blackout::Blackouts::load(&config.blackouts) {
+		Ok(blackouts) => blackouts,
+		Err(e) => {
+			eprintln!("Failed to load blackouts from {}: {}", &args.config_path, e);
+			return INVALID_CONFIG;
+		}
+	};
+	let dialect = match config.db.dialect() {
+		Ok(dialect) => dialect,
+		Err(e) => {
+			eprintln!("Failed to parse {}: {}", &args.config_path, e);
+			return INVALID_CONFIG;
+		}
+	};
+	let syntax = events::Syntax::new(dialect.as_ref());
+	let validation = if args.check_sql {
+		events::Validation::Parse(dialect)
+	} else {
+		events::Validation::None
+	};
+
+	let events = match events::parse(&args.events_path, syntax, validation, Some(&blackouts.names())).await {
+		Ok(events) => events,
+		Err(e) if e.is::<events::error::EventErrors>() => {
+			eprintln!("{}", e);
+			return INVALID_EVENTS;
+		},
+		Err(e) => {
+			eprintln!("Failed to read {}: {}", &args.events_path, e);
+			return INVALID_CONFIG;
+		}
+	};
+	// Statements the embedded SQL parser can't read may still be valid, so they don't fail the check
+	let warnings: Vec<_> = events.iter().flat_map(|evt| &evt.warnings).collect();
+	for warning in &warnings {
+		eprintln!("{}\n", warning);
+	}
+	match warnings.len() {
+		0 => println!("{}: {} events OK", &args.events_path, events.len()),
+		1 => println!("{}: {} events OK, 1 warning", &args.events_path, events.len()),
+		n => println!("{}: {} events OK, {} warnings", &args.events_path, events.len(), n)
+	}
+	OK
+}
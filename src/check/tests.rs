@@ -0,0 +1,117 @@
+use crate::args::{Args, Command};
+use crate::test_util::{tree, Tree};
+use super::{check, INVALID_CONFIG, INVALID_EVENTS, OK};
+
+const CONFIG: &str = r#"{
+	"db": {"user": "admin", "database": "MyDatabase", "driver": "mariadb"},
+	"log": {},
+	"blackouts": {"nightly": {"windows": ["* 0-6 * * *"]}}
+}"#;
+
+/// Check a config.json and events.conf written to a temporary directory
+async fn run(dir: &Tree, sql: bool) -> i32 {
+	check(&Args {
+		command: Command::Check,
+		check_sql: sql,
+		verbose: false,
+		config_path: dir.file("config.json"),
+		events_path: dir.file("events.conf"),
+		state_path: dir.file("state.json")
+	}).await
+}
+
+#[tokio::test]
+async fn valid() {
+	let dir = tree("check_valid", &[
+		("config.json", CONFIG),
+		("events.conf", "Expire sessions:\n0 * * * * @blackout(nightly)\n\tDELETE FROM Sessions WHERE Expired = 1;\n")
+	]);
+	assert_eq!(run(&dir, false).await, OK);
+	assert_eq!(run(&dir, true).await, OK);
+}
+
+#[tokio::test]
+async fn invalid_events() {
+	for (name, events) in [
+		("check_cron", "Expire sessions:\n60 * * * *\n\tSELECT 1;\n"),
+		("check_blackout", "Expire sessions:\n0 * * * * @blackout(weekend)\n\tSELECT 1;\n"),
+		("check_include", "include missing.conf\n")
+	] {
+		let dir = tree(name, &[("config.json", CONFIG), ("events.conf", events)]);
+		assert_eq!(run(&dir, false).await, INVALID_EVENTS, "{}", events);
+	}
+}
+
+#[tokio::test]
+async fn invalid_config() {
+	let events = "Expire sessions:\n0 * * * *\n\tSELECT 1;\n";
+	for (name, config) in [
+		("check_json", "{"),
+		("check_driver", r#"{"db": {"user": "admin", "database": "MyDatabase", "driver": "oracle"}, "log": {}}"#),
+		("check_window", r#"{"db": {"user": "admin", "database": "MyDatabase"}, "log": {}, "blackouts": {"nightly": {"windows": ["H * * * *"]}}}"#)
+	] {
+		let dir = tree(name, &[("config.json", config), ("events.conf", events)]);
+		assert_eq!(run(&dir, false).await, INVALID_CONFIG, "{}", config);
+	}
+
+	// Files that can't be read
+	let dir = tree("check_missing_events", &[("config.json", CONFIG)]);
+	assert_eq!(run(&dir, false).await, INVALID_CONFIG);
+	let dir = tree("check_missing_config", &[("events.conf", events)]);
+	assert_eq!(run(&dir, false).await, INVALID_CONFIG);
+}
+
+#[tokio::test]
+async fn sql() {
+	let dir = tree("check_sql", &[
+		("config.json", CONFIG),
+		("events.conf", "Expire sessions:\n0 * * * *\n\tDELETE FROM WHERE Expired = 1;\n")
+	]);
+	// SQL is only syntax-checked with --sql, and statements the embedded parser can't read are only warnings
+	assert_eq!(run(&dir, false).await, OK);
+	assert_eq!(run(&dir, true).await, OK);
+
+	// Warnings don't hide errors
+	let dir = tree("check_sql_errors", &[
+		("config.json", CONFIG),
+		("events.conf", "Expire sessions:\n0 * * * *\n\tDELETE FROM WHERE Expired = 1;\n\nExpire tokens:\n60 * * * *\n\tSELECT 1;\n")
+	]);
+	assert_eq!(run(&dir, true).await, INVALID_EVENTS);
+}
+
+/// Untagged code blocks of the README that are events.conf examples
+fn readme_examples() -> Vec<String> {
+	let mut examples = Vec::new();
+	let mut block: Option<Vec<&str>> = None;
+	for line in include_str!("../../README.md").lines() {
+		match (&mut block, line) {
+			(None, "```") => block = Some(Vec::new()),
+			(None, _) if line.starts_with("```") => block = Some(vec!["```"]), // Tagged, i.e jsonc
+			(Some(lines), "```") => {
+				if lines.first() != Some(&"```") {
+					examples.push(lines.join("\n") + "\n");
+				}
+				block = None;
+			},
+			(Some(lines), _) => lines.push(line),
+			_ => {}
+		}
+	}
+	// Error output, include lines and the syntax overview aren't examples that can be checked
+	examples.retain(|e| !e.starts_with("error:") && !e.starts_with("include") && !e.contains("\ninterval\n"));
+	examples
+}
+
+#[tokio::test]
+async fn readme() {
+	let examples = readme_examples();
+	assert!(examples.len() >= 4, "{:?}", examples);
+	for (i, example) in examples.iter().enumerate() {
+		let dir = tree(&format!("check_readme_{}", i), &[
+			("config.json", CONFIG),
+			("events.conf", example),
+			("cleanup/expire_sessions.sql", "DELETE FROM Sessions WHERE Expired = 1;\n")
+		]);
+		assert_eq!(run(&dir, true).await, OK, "{}", example);
+	}
+}
@@ -1,4 +1,4 @@
-use std::{fs::File, error::Error};
+use std::{collections::BTreeMap, fs::File, error::Error};
 use serde::Deserialize;
 use crate::db::error::DBConfigError;
 
@@ -7,7 +7,10 @@ pub struct Config {
 	// Config for connecting to the MariaDB/MySQL database
 	pub db: crate::db::Config,
 	// Config for log outputs
-	pub log: crate::logging::Config
+	pub log: crate::logging::Config,
+	// Named blackouts during which events don't run
+	#[serde(default)]
+	pub blackouts: BTreeMap<String, crate::blackout::Config>
 }
 
 impl Config {
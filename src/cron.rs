@@ -1,48 +1,217 @@
 use std::fmt::Display;
 
-use chrono::{DateTime, Local, Timelike, Datelike};
+use chrono::{DateTime, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Timelike, Datelike, Utc};
+use chrono_tz::Tz;
+
+use parsing::CronRange;
 
 pub mod parsing;
 pub mod error;
+mod next;
+mod every;
+mod at;
+mod describe;
+
+pub use every::EveryInterval;
+pub(crate) use every::parse_duration;
+pub use at::OneShot;
+#[cfg(test)]
+mod tests;
+
+/// When an event runs: on a cron interval, at a fixed interval (@every), or once (@at)
+#[derive(Debug)]
+pub enum Schedule {
+	Cron(CronInterval),
+	Every(EveryInterval),
+	At(OneShot)
+}
+
+impl Schedule {
+	/// Whether the schedule should fire immediately when my_timers starts
+	pub fn startup(&self) -> bool {
+		match self {
+			Self::Cron(interval) => interval.startup,
+			Self::Every(interval) => interval.startup,
+			Self::At(_) => false
+		}
+	}
+
+	/// How runs missed while my_timers wasn't running are handled
+	pub fn catchup(&self) -> CatchUp {
+		match self {
+			Self::Cron(interval) => interval.catchup,
+			Self::Every(interval) => interval.catchup,
+			// Overdue one-shot events are handled separately, see Scheduler::new
+			Self::At(_) => CatchUp::None
+		}
+	}
+
+	/// Whether each run is persisted, for catching up on missed runs or marking one-shot events done
+	pub fn records_runs(&self) -> bool {
+		matches!(self, Self::At(_)) || self.catchup() != CatchUp::None
+	}
+
+	/// Replace H values with offsets derived from the event's label, see CronInterval::resolve_hashes
+	pub fn resolve_hashes(&mut self, label: &str) {
+		if let Self::Cron(interval) = self {
+			interval.resolve_hashes(label);
+		}
+	}
+
+	/// English description of the schedule
+	pub fn describe(&self) -> String {
+		match self {
+			Self::Cron(interval) => interval.describe(),
+			Self::Every(interval) => interval.describe(),
+			Self::At(at) => at.describe()
+		}
+	}
+
+	/// The next instant the schedule fires strictly after `after`
+	pub fn next_after<T: TimeZone>(&self, after: &DateTime<T>) -> Option<DateTime<Utc>> {
+		match self {
+			Self::Cron(interval) => interval.next_after(after),
+			Self::Every(interval) => interval.next_after(after),
+			Self::At(at) => at.next_after(after)
+		}
+	}
+
+	/// Iterator over the instants the schedule fires at after `after`
+	pub fn upcoming<T: TimeZone>(&self, after: &DateTime<T>) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+		std::iter::successors(self.next_after(after), |last| self.next_after(last))
+	}
+}
+
+impl Display for Schedule {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Cron(interval) => interval.fmt(f),
+			Self::Every(interval) => interval.fmt(f),
+			Self::At(at) => at.fmt(f)
+		}
+	}
+}
 
 #[derive(Debug)]
 pub struct CronInterval {
+	second: Option<CronValue>, // Optional leading seconds field, 5 field intervals fire at 0 seconds
 	minute: CronValue,
 	hour: CronValue,
 	day: CronValue, // day of month
 	month: CronValue,
 	weekday: CronValue,
-	pub startup: bool // Whether the interval should fire immediately when my_timers starts
+	pub startup: bool, // Whether the interval should fire immediately when my_timers starts
+	reboot: bool, // Whether the interval ONLY fires when my_timers starts (@reboot)
+	tz: Option<Tz>, // Timezone the interval is evaluated in (CRON_TZ=/@tz), defaults to the host's timezone
+	pub catchup: CatchUp // How runs missed while my_timers wasn't running are handled
+}
+
+/// Policy for runs that were missed while my_timers wasn't running
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CatchUp {
+	#[default]
+	None, // Missed runs are skipped
+	Once, // Missed runs are collapsed into a single run at startup
+	All(u32) // Every missed run is made up at startup, up to a limit
 }
 
 impl CronInterval {
-	pub fn match_time(&self, now: &DateTime<Local>) -> bool {
-		self.minute.compare(now.minute()) &&
-		self.hour.compare(now.hour()) &&
+	/// Whether the interval matches the wall clock time of an instant in the interval's timezone.
+	/// See next_after for scheduling that accounts for DST transitions.
+	pub fn match_time<T: TimeZone>(&self, now: &DateTime<T>) -> bool {
+		self.match_local(&self.local_time(now))
+	}
+
+	/// Wall clock time of an instant in the interval's timezone
+	fn local_time<T: TimeZone>(&self, t: &DateTime<T>) -> NaiveDateTime {
+		match self.tz {
+			Some(tz) => t.with_timezone(&tz).naive_local(),
+			None => t.with_timezone(&Local).naive_local()
+		}
+	}
+
+	/// Instant(s) referred to by a wall clock time in the interval's timezone
+	fn instant_of(&self, local: &NaiveDateTime) -> LocalResult<DateTime<Utc>> {
+		match self.tz {
+			Some(tz) => tz.from_local_datetime(local).map(|t| t.with_timezone(&Utc)),
+			None => Local.from_local_datetime(local).map(|t| t.with_timezone(&Utc))
+		}
+	}
+
+	/// Seconds field, defaulting to 0 for 5 field intervals
+	fn second(&self) -> &CronValue {
+		static ZERO: CronValue = CronValue::Value(0);
+		self.second.as_ref().unwrap_or(&ZERO)
+	}
+
+	/// Smallest step between fire times (1 second with a seconds field, otherwise 1 minute)
+	fn resolution(&self) -> Duration {
+		if self.second.is_some() {
+			Duration::seconds(1)
+		} else {
+			Duration::minutes(1)
+		}
+	}
+
+	/// Whether the interval fires periodically throughout the day
+	fn is_periodic(&self) -> bool {
+		self.minute.is_wildcard() || self.hour.is_wildcard()
+	}
+
+	// Match a wall clock time in the interval's timezone
+	fn match_local(&self, now: &NaiveDateTime) -> bool {
+		!self.reboot &&
+		// Seconds are ignored for 5 field intervals, which match the whole minute
+		self.second.as_ref().is_none_or(|s| s.compare(now.second(), Self::second_range())) &&
+		self.minute.compare(now.minute(), Self::minute_range()) &&
+		self.hour.compare(now.hour(), Self::hour_range()) &&
+		self.match_date(now.date())
+	}
+
+	// Match a date in the interval's timezone
+	fn match_date(&self, date: NaiveDate) -> bool {
+		self.month.compare(date.month(), Self::month_range()) &&
 		// See https://crontab.guru/cron-bug.html
-		if self.day == CronValue::Every || self.weekday == CronValue::Every { // If date or weekday is *, evaluate them as an intersection
-			self.day.compare(now.day()) &&
-			(
-				self.weekday.compare(now.weekday().number_from_monday() % 7) || // 0 == Sunday
-				self.weekday.compare(now.weekday().number_from_monday()) // 7 == Sunday
-			)
+		if self.day.is_wildcard() || self.weekday.is_wildcard() { // If date or weekday is *, evaluate them as an intersection
+			self.day.compare_day(date) && self.weekday.compare_weekday(date)
 		} else { // If neither date or weekday is *, evaluate them as a union
-			self.day.compare(now.day()) ||
-			self.weekday.compare(now.weekday().number_from_monday() % 7) ||
-			self.weekday.compare(now.weekday().number_from_monday())
+			self.day.compare_day(date) || self.weekday.compare_weekday(date)
 		}
 	}
 }
 
 impl Display for CronInterval {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if let Some(tz) = self.tz {
+			write!(f, "CRON_TZ={} ", tz)?;
+		}
+		if self.reboot {
+			return write!(f, "@reboot");
+		}
+		if let Some(second) = &self.second {
+			write!(f, "{} ", second)?;
+		}
 		write!(f, "{} {} {} {} {}{}",
 		self.minute,
 		self.hour,
 		self.day,
 		self.month,
 		self.weekday,
-		if self.startup { " @startup" } else { "" })
+		if self.startup { " @startup" } else { "" })?;
+		if self.catchup != CatchUp::None {
+			write!(f, " @catchup({})", self.catchup)?;
+		}
+		Ok(())
+	}
+}
+
+impl Display for CatchUp {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::None => write!(f, "none"),
+			Self::Once => write!(f, "once"),
+			Self::All(limit) => write!(f, "all,{}", limit)
+		}
 	}
 }
 
@@ -52,16 +221,92 @@ pub enum CronValue {
 	Value(u32), // Single number
 	Set(Vec<u32>), // Comma-separated values
 	Range((u32, u32)), // Range (start, stop)
+	Step(Box<CronValue>, u32), // Stepped value (start, step), where start is *, a range, or a single value
+	List(Vec<CronValue>), // Comma-separated mix of values, ranges and steps
+	LastDay, // Last day of the month (L), day of month only
+	NearestWeekday(u32), // Weekday nearest to a day of the month (15W), day of month only
+	NthWeekday((u32, u32)), // Nth occurrence of a weekday in the month (weekday, n) (2#2), weekday only
+	LastWeekday(u32), // Last occurrence of a weekday in the month (5L), weekday only
+	Hash(Option<(u32, u32)>) // Value derived from the event label (H, H(0-29)), replaced by resolve_hashes after parsing
 }
 
 impl CronValue {
 	// Compare a time value with a cron field
-	fn compare(&self, value: u32) -> bool {
+	fn compare(&self, value: u32, range: CronRange) -> bool {
 		match self {
 			Self::Every => true,
 			Self::Value(n) => &value == n,
 			Self::Set(set) => set.binary_search(&value).is_ok(),
-			Self::Range((start, end)) => &value >= start && &value <= end
+			Self::Range((start, end)) => &value >= start && &value <= end,
+			Self::Step(start, step) => {
+				// A single starting value steps until the end of the field's range
+				let (first, last) = match start.as_ref() {
+					Self::Value(n) => (*n, range.1),
+					Self::Range((n1, n2)) => (*n1, *n2),
+					_ => (range.0, range.1)
+				};
+				value >= first && value <= last && (value - first).is_multiple_of(*step)
+			},
+			Self::List(list) => list.iter().any(|v| v.compare(value, range)),
+			// Date-dependent values are evaluated by compare_day/compare_weekday
+			Self::LastDay | Self::NearestWeekday(_) | Self::NthWeekday(_) | Self::LastWeekday(_) => false,
+			// Unresolved hashes never match
+			Self::Hash(_) => false
+		}
+	}
+
+	// Compare a date with a day of month field
+	fn compare_day(&self, date: NaiveDate) -> bool {
+		let last = days_in_month(date);
+		match self {
+			Self::LastDay => date.day() == last,
+			Self::NearestWeekday(n) => {
+				if *n > last {
+					return false;
+				}
+				// Move to the nearest weekday without leaving the month
+				let target = date.with_day(*n).unwrap();
+				let nearest = match target.weekday().num_days_from_monday() {
+					5 if *n == 1 => n + 2, // Saturday the 1st -> Monday the 3rd
+					5 => n - 1, // Saturday -> Friday
+					6 if *n == last => n - 2, // Sunday the last -> Friday
+					6 => n + 1, // Sunday -> Monday
+					_ => *n
+				};
+				date.day() == nearest
+			},
+			Self::List(list) => list.iter().any(|v| v.compare_day(date)),
+			_ => self.compare(date.day(), CronInterval::day_range())
+		}
+	}
+
+	// Compare a date with a weekday field
+	fn compare_weekday(&self, date: NaiveDate) -> bool {
+		let weekday = date.weekday().num_days_from_sunday();
+		match self {
+			Self::NthWeekday((d, n)) => d % 7 == weekday && (date.day() - 1) / 7 + 1 == *n,
+			Self::LastWeekday(d) => d % 7 == weekday && date.day() + 7 > days_in_month(date),
+			Self::List(list) => list.iter().any(|v| v.compare_weekday(date)),
+			_ => {
+				let range = CronInterval::weekday_range();
+				self.compare(weekday, range) || // 0 == Sunday
+				(weekday == 0 && self.compare(7, range)) // 7 == Sunday
+			}
+		}
+	}
+
+	// Smallest value >= from in a field's range that matches the field
+	fn next_value(&self, from: u32, range: CronRange) -> Option<u32> {
+		(from..=range.1).find(|v| self.compare(*v, range))
+	}
+
+	// Whether a field starts with an asterisk (* or */n),
+	// matching how cron decides between day/weekday union and intersection
+	fn is_wildcard(&self) -> bool {
+		match self {
+			Self::Every => true,
+			Self::Step(start, _) => start.as_ref() == &Self::Every,
+			_ => false
 		}
 	}
 }
@@ -75,7 +320,28 @@ impl Display for CronValue {
 				let s: Vec<String> = set.iter().map(|n| n.to_string()).collect();
 				s.join(",").fmt(f)
 			},
-			Self::Range((start, end)) => write!(f, "{}-{}", start, end)
+			Self::Range((start, end)) => write!(f, "{}-{}", start, end),
+			Self::Step(start, step) => write!(f, "{}/{}", start, step),
+			Self::List(list) => {
+				let s: Vec<String> = list.iter().map(|v| v.to_string()).collect();
+				s.join(",").fmt(f)
+			},
+			Self::LastDay => write!(f, "L"),
+			Self::NearestWeekday(n) => write!(f, "{}W", n),
+			Self::NthWeekday((d, n)) => write!(f, "{}#{}", d, n),
+			Self::LastWeekday(d) => write!(f, "{}L", d),
+			Self::Hash(None) => write!(f, "H"),
+			Self::Hash(Some((start, end))) => write!(f, "H({}-{})", start, end)
 		}
 	}
 }
+
+/// Number of days in a date's month
+fn days_in_month(date: NaiveDate) -> u32 {
+	let (year, month) = if date.month() == 12 {
+		(date.year() + 1, 1)
+	} else {
+		(date.year(), date.month() + 1)
+	};
+	NaiveDate::from_ymd_opt(year, month, 1).unwrap().pred_opt().unwrap().day()
+}
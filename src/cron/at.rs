@@ -0,0 +1,71 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{DateTime, Local, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use super::error::{CronParseError, Token};
+use super::parsing::{parse_datetime, parse_tz, tokens};
+
+/// One-shot schedule (i.e `@at 2026-11-01T03:00`), firing once at an absolute time
+#[derive(Debug)]
+pub struct OneShot {
+	pub time: DateTime<Utc>,
+	tz: Option<Tz> // Timezone a time without an offset is in (@tz), defaults to the host's timezone
+}
+
+impl OneShot {
+	/// The time the event fires at if it's strictly after `after`
+	pub fn next_after<T: TimeZone>(&self, after: &DateTime<T>) -> Option<DateTime<Utc>> {
+		Some(self.time).filter(|t| t > &after.with_timezone(&Utc))
+	}
+}
+
+impl OneShot {
+	/// English description of the schedule (i.e "once at 2026-11-01T03:00:00+01:00")
+	pub fn describe(&self) -> String {
+		match self.tz {
+			Some(tz) => format!("once at {}", self.time.with_timezone(&tz).to_rfc3339()),
+			None => format!("once at {}", self.time.with_timezone(&Local).to_rfc3339())
+		}
+	}
+}
+
+impl FromStr for OneShot {
+	type Err = CronParseError;
+
+	/// Parse `@at <date and time>`, followed by an optional @tz(...) tag
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut values = tokens(s);
+		let Some((offset, "@at")) = values.pop_front() else {
+			return Err(CronParseError::SyntaxError(format!("{} - expected @at", s), None));
+		};
+		let Some((time_offset, time)) = values.pop_front() else {
+			let e = "@at requires a date and time (i.e @at 2026-11-01T03:00)".into();
+			return Err(CronParseError::SyntaxError(e, Some(Token::new("@at", offset))));
+		};
+		let mut tz = None;
+		for (offset, tag) in values {
+			match tag {
+				_ if tag.starts_with("@tz(") && tag.ends_with(')') => {
+					tz = Some(parse_tz(&tag[4..tag.len()-1]).map_err(|e| e.offset(offset + 4))?);
+				},
+				_ => return Err(CronParseError::SyntaxError(format!("unexpected token {}", tag), Some(Token::new(tag, offset))))
+			}
+		}
+		Ok(Self {
+			time: parse_datetime(time, tz).map_err(|e| e.offset(time_offset))?,
+			tz
+		})
+	}
+}
+
+impl Display for OneShot {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		const FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+		match self.tz {
+			Some(tz) => write!(f, "@at {} @tz({})", self.time.with_timezone(&tz).format(FORMAT), tz),
+			None => write!(f, "@at {}", self.time.with_timezone(&Local).format(FORMAT))
+		}
+	}
+}
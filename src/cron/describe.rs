@@ -0,0 +1,167 @@
+use super::{CatchUp, CronInterval, CronValue};
+
+/// A cron field, as named in descriptions
+struct Unit {
+	name: &'static str,
+	// Names of the field's values (i.e Monday), which are described without the unit's name
+	names: Option<&'static [&'static str]>
+}
+
+const SECOND: Unit = Unit { name: "second", names: None };
+const MINUTE: Unit = Unit { name: "minute", names: None };
+const HOUR: Unit = Unit { name: "hour", names: None };
+const DAY: Unit = Unit { name: "day-of-month", names: None };
+const MONTH: Unit = Unit {
+	name: "month",
+	names: Some(&["", "January", "February", "March", "April", "May", "June",
+		"July", "August", "September", "October", "November", "December"])
+};
+const WEEKDAY: Unit = Unit {
+	name: "day-of-week",
+	names: Some(&["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"])
+};
+
+impl Unit {
+	fn value(&self, n: u32) -> String {
+		match self.names {
+			Some(names) => names.get(n as usize).copied().unwrap_or("?").to_string(),
+			None => n.to_string()
+		}
+	}
+}
+
+impl CronInterval {
+	/// English description of the interval (i.e "at minute 0 past every hour on Monday through Friday")
+	pub fn describe(&self) -> String {
+		if self.reboot {
+			return "when my_timers starts".into();
+		}
+		let mut description = self.describe_time();
+		// Day of month and weekday match as a union when both are set, see match_date
+		let day = (self.day != CronValue::Every).then(|| format!("on {}", describe_day(&self.day)));
+		let weekday = (self.weekday != CronValue::Every).then(|| format!("on {}", describe_value(&self.weekday, &WEEKDAY)));
+		match (day, weekday) {
+			(Some(day), Some(weekday)) => {
+				let join = if self.day.is_wildcard() || self.weekday.is_wildcard() { "and" } else { "or" };
+				description += &format!(" {} {} {}", day, join, weekday);
+			},
+			(Some(date), None) | (None, Some(date)) => description += &format!(" {}", date),
+			(None, None) => {}
+		}
+		if self.month != CronValue::Every {
+			description += &format!(" in {}", describe_value(&self.month, &MONTH));
+		}
+		if let Some(tz) = self.tz {
+			description += &format!(" ({} time)", tz);
+		}
+		if self.startup {
+			description += ", and when my_timers starts";
+		}
+		if let Some(catchup) = self.catchup.describe() {
+			description += &format!(", {}", catchup);
+		}
+		description
+	}
+
+	/// Describe the second, minute and hour fields
+	fn describe_time(&self) -> String {
+		let second = self.second.as_ref().unwrap_or(&CronValue::Value(0)).clone();
+		// Single times read as a clock time
+		if let (CronValue::Value(s), CronValue::Value(m), CronValue::Value(h)) = (&second, &self.minute, &self.hour) {
+			return match self.second {
+				Some(_) => format!("at {:02}:{:02}:{:02}", h, m, s),
+				None => format!("at {:02}:{:02}", h, m)
+			};
+		}
+		// Otherwise each field is described past the next, leaving out "past every ..." after a wildcard
+		let mut fields = vec![(&second, &SECOND), (&self.minute, &MINUTE), (&self.hour, &HOUR)];
+		if self.second.is_none() {
+			fields.remove(0);
+		}
+		let mut description = format!("at {}", describe_value(fields[0].0, fields[0].1));
+		for window in fields.windows(2) {
+			let (prev, (value, unit)) = (window[0].0, window[1]);
+			if *prev == CronValue::Every && *value == CronValue::Every {
+				continue;
+			}
+			description += &format!(" past {}", describe_value(value, unit));
+		}
+		description
+	}
+}
+
+impl CatchUp {
+	/// English description of the policy, if missed runs are made up
+	pub(super) fn describe(&self) -> Option<String> {
+		match self {
+			Self::None => None,
+			Self::Once => Some("running once after missed runs".into()),
+			Self::All(limit) => Some(format!("making up to {} missed runs", limit))
+		}
+	}
+}
+
+/// Describe a day of month field, including L and W
+fn describe_day(v: &CronValue) -> String {
+	match v {
+		CronValue::LastDay => "the last day of the month".into(),
+		CronValue::NearestWeekday(n) => format!("the weekday nearest day-of-month {}", n),
+		CronValue::List(list) => join(list.iter().map(describe_day).collect()),
+		_ => describe_value(v, &DAY)
+	}
+}
+
+/// Describe a cron field
+fn describe_value(v: &CronValue, unit: &Unit) -> String {
+	// Named values are described by name alone (i.e Monday through Friday)
+	let value = |n: u32| match unit.names {
+		Some(_) => unit.value(n),
+		None => format!("{} {}", unit.name, n)
+	};
+	match v {
+		CronValue::Every => format!("every {}", unit.name),
+		CronValue::Value(n) => value(*n),
+		CronValue::Set(set) => match unit.names {
+			Some(_) => join(set.iter().map(|n| unit.value(*n)).collect()),
+			None => format!("{} {}", unit.name, join(set.iter().map(|n| n.to_string()).collect()))
+		},
+		CronValue::Range((start, end)) => match unit.names {
+			Some(_) => format!("{} through {}", unit.value(*start), unit.value(*end)),
+			None => format!("every {} from {} through {}", unit.name, start, end)
+		},
+		CronValue::Step(start, step) => {
+			let every = format!("every {} {}", ordinal(*step), unit.name);
+			match start.as_ref() {
+				CronValue::Value(n) => format!("{} from {}", every, unit.value(*n)),
+				CronValue::Range((n1, n2)) => format!("{} from {} through {}", every, unit.value(*n1), unit.value(*n2)),
+				_ => every
+			}
+		},
+		CronValue::List(list) => join(list.iter().map(|v| describe_value(v, unit)).collect()),
+		CronValue::LastDay | CronValue::NearestWeekday(_) => describe_day(v),
+		CronValue::NthWeekday((d, n)) => format!("the {} {} of the month", ordinal(*n), unit.value(*d)),
+		CronValue::LastWeekday(d) => format!("the last {} of the month", unit.value(*d)),
+		CronValue::Hash(_) => format!("a {} picked from the event's name", unit.name)
+	}
+}
+
+/// Join phrases as an English list (i.e "a, b and c")
+fn join(mut phrases: Vec<String>) -> String {
+	match phrases.pop() {
+		Some(last) if !phrases.is_empty() => format!("{} and {}", phrases.join(", "), last),
+		Some(last) => last,
+		None => String::new()
+	}
+}
+
+/// English ordinal of a number (i.e 1st, 2nd, 3rd, 11th)
+fn ordinal(n: u32) -> String {
+	let suffix = match (n % 10, n % 100) {
+		(_, 11..=13) => "th",
+		(1, _) => "st",
+		(2, _) => "nd",
+		(3, _) => "rd",
+		_ => "th"
+	};
+	format!("{}{}", n, suffix)
+}
@@ -5,25 +5,70 @@ use std::num::ParseIntError;
 use super::CronValue;
 use super::parsing::CronRange;
 
+/// Text an error refers to, and its byte offset in the parsed interval
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+	pub text: String,
+	pub offset: usize
+}
+
+impl Token {
+	pub(super) fn new(text: &str, offset: usize) -> Self {
+		Self {
+			text: text.to_string(),
+			offset
+		}
+	}
+}
+
 #[derive(Debug)]
 pub enum CronParseError {
-	MalformedTokens(String),
-	ParseIntError(ParseIntError),
-	OutOfRange(Box<CronValue>, Box<CronRange>),
-	SyntaxError(String)
+	MalformedTokens(String, Token),
+	ParseIntError(Token, ParseIntError),
+	OutOfRange(Box<CronValue>, Box<CronRange>, Option<Token>),
+	SyntaxError(String, Option<Token>)
 }
 
 impl Display for CronParseError {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		match self {
-			Self::MalformedTokens(e) => write!(f, "Malformed tokens: {}", e),
-			Self::ParseIntError(e) => e.fmt(f),
-			Self::OutOfRange(value, range) => {
-				Self::SyntaxError(format!("value {:?} exceeds field range {:?}", value, range)).fmt(f)
+			Self::MalformedTokens(e, _) => write!(f, "Malformed tokens: {}", e),
+			Self::ParseIntError(token, e) => write!(f, "Invalid cron value \"{}\": {}", token.text, e),
+			Self::OutOfRange(value, range, _) => {
+				write!(f, "Invalid cron syntax: value {} exceeds field range {}-{}", value, range.0, range.1)
 			},
-			Self::SyntaxError(e) => write!(f, "Invalid cron syntax: {}", e)
+			Self::SyntaxError(e, _) => write!(f, "Invalid cron syntax: {}", e)
 		}
 	}
 }
 
 impl Error for CronParseError {}
+
+impl CronParseError {
+	/// The offending token, if the error refers to one
+	pub fn token(&self) -> Option<&Token> {
+		match self {
+			Self::MalformedTokens(_, token) | Self::ParseIntError(token, _) => Some(token),
+			Self::OutOfRange(_, _, token) | Self::SyntaxError(_, token) => token.as_ref()
+		}
+	}
+
+	/// Move the error's token `n` bytes later, for errors from parsing part of a larger string
+	pub(super) fn offset(mut self, n: usize) -> Self {
+		match &mut self {
+			Self::MalformedTokens(_, token) | Self::ParseIntError(token, _) => token.offset += n,
+			Self::OutOfRange(_, _, token) | Self::SyntaxError(_, token) => if let Some(token) = token {
+				token.offset += n;
+			}
+		}
+		self
+	}
+
+	/// Point the error at `text` if it doesn't refer to a token yet
+	pub(super) fn or_token(mut self, text: &str, offset: usize) -> Self {
+		if let Self::OutOfRange(_, _, token @ None) | Self::SyntaxError(_, token @ None) = &mut self {
+			*token = Some(Token::new(text, offset));
+		}
+		self
+	}
+}
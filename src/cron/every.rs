@@ -0,0 +1,155 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, DurationRound, Local, TimeZone, Utc};
+
+use super::error::{CronParseError, Token};
+use super::parsing::{parse_datetime, tokens};
+use super::CatchUp;
+
+/// Fixed-interval schedule (i.e `@every 2h30m`), firing every period after an anchor
+#[derive(Debug)]
+pub struct EveryInterval {
+	period: Duration,
+	anchor: Option<DateTime<Utc>>, // Fixed epoch the interval is measured from (@anchor), defaults to when my_timers started
+	started: DateTime<Utc>, // When the interval was parsed, which is when my_timers started
+	pub startup: bool, // Whether the interval should fire immediately when my_timers starts
+	pub catchup: CatchUp // How runs missed while my_timers wasn't running are handled
+}
+
+impl EveryInterval {
+	/// The next instant the interval fires strictly after `after`.
+	/// Fire times are the anchor plus any whole number of periods, including those before the anchor.
+	pub fn next_after<T: TimeZone>(&self, after: &DateTime<T>) -> Option<DateTime<Utc>> {
+		let anchor = self.anchor.unwrap_or(self.started);
+		let period = self.period.num_seconds();
+		// Whole seconds since the anchor, rounded down rather than towards zero for times before the anchor
+		let elapsed = after.with_timezone(&Utc) - anchor;
+		let elapsed = elapsed.num_seconds() - i64::from(elapsed.subsec_nanos() < 0);
+		let n = elapsed.div_euclid(period) + 1;
+		anchor.checked_add_signed(Duration::seconds(n.checked_mul(period)?))
+	}
+}
+
+impl EveryInterval {
+	/// English description of the interval (i.e "every 2 hours and 30 minutes")
+	pub fn describe(&self) -> String {
+		const UNIT_NAMES: [&str; 4] = ["day", "hour", "minute", "second"];
+		let mut seconds = self.period.num_seconds();
+		let mut parts = Vec::new();
+		for ((_, scale), name) in DURATION_UNITS.iter().zip(UNIT_NAMES) {
+			let n = seconds / scale;
+			seconds %= scale;
+			match n {
+				0 => {},
+				1 => parts.push(format!("1 {}", name)),
+				_ => parts.push(format!("{} {}s", n, name))
+			}
+		}
+		let mut description = match parts.as_slice() {
+			[one] => format!("every {}", one.trim_start_matches("1 ")),
+			_ => format!("every {}", parts.join(" and "))
+		};
+		match self.anchor {
+			Some(anchor) => description += &format!(" from {}", anchor.with_timezone(&Local).to_rfc3339()),
+			None => description += " from when my_timers started"
+		}
+		if self.startup {
+			description += ", and when my_timers starts";
+		}
+		if let Some(catchup) = self.catchup.describe() {
+			description += &format!(", {}", catchup);
+		}
+		description
+	}
+}
+
+impl FromStr for EveryInterval {
+	type Err = CronParseError;
+
+	/// Parse `@every <duration>`, followed by optional tags
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::parse(s, Utc::now().duration_trunc(Duration::seconds(1)).unwrap())
+	}
+}
+
+impl EveryInterval {
+	/// Parse `@every <duration>` as if my_timers started at `started`
+	pub(super) fn parse(s: &str, started: DateTime<Utc>) -> Result<Self, CronParseError> {
+		let mut values = tokens(s);
+		let Some((offset, "@every")) = values.pop_front() else {
+			return Err(CronParseError::SyntaxError(format!("{} - expected @every", s), None));
+		};
+		let Some((period_offset, period)) = values.pop_front() else {
+			let e = "@every requires a duration (i.e @every 90s)".into();
+			return Err(CronParseError::SyntaxError(e, Some(Token::new("@every", offset))));
+		};
+		let mut interval = Self {
+			period: parse_duration(period).map_err(|e| e.offset(period_offset))?,
+			anchor: None,
+			started,
+			startup: false,
+			catchup: CatchUp::None
+		};
+		for (offset, tag) in values {
+			match tag {
+				"@startup" => interval.startup = true,
+				_ if tag.starts_with("@anchor(") && tag.ends_with(')') => {
+					interval.anchor = Some(parse_datetime(&tag[8..tag.len()-1], None).map_err(|e| e.offset(offset + 8))?);
+				},
+				_ if tag.starts_with("@catchup(") && tag.ends_with(')') => {
+					interval.catchup = tag[9..tag.len()-1].parse().map_err(|e: CronParseError| e.offset(offset + 9))?;
+				},
+				_ => return Err(CronParseError::SyntaxError(format!("unexpected token {}", tag), Some(Token::new(tag, offset))))
+			}
+		}
+		Ok(interval)
+	}
+}
+
+/// Units accepted in an @every duration, largest first
+const DURATION_UNITS: &[(char, i64)] = &[('d', 86400), ('h', 3600), ('m', 60), ('s', 1)];
+
+/// Parse a duration made of whole numbers of days, hours, minutes and seconds (i.e 90s, 2h30m, 1d)
+pub(crate) fn parse_duration(s: &str) -> Result<Duration, CronParseError> {
+	let invalid = || CronParseError::SyntaxError(format!("invalid duration {} (expected i.e 90s, 2h30m, 1d)", s), Some(Token::new(s, 0)));
+	let mut seconds: i64 = 0;
+	let mut rest = s;
+	while !rest.is_empty() {
+		let digits = rest.find(|c: char| !c.is_ascii_digit()).filter(|d| *d > 0).ok_or_else(invalid)?;
+		let (n, tail) = rest.split_at(digits);
+		let n: i64 = n.parse().map_err(|e| CronParseError::ParseIntError(Token::new(n, s.len() - rest.len()), e))?;
+		let mut chars = tail.chars();
+		let unit = chars.next().unwrap();
+		let (_, scale) = DURATION_UNITS.iter().find(|(u, _)| *u == unit).ok_or_else(invalid)?;
+		seconds = n.checked_mul(*scale).and_then(|n| seconds.checked_add(n)).ok_or_else(invalid)?;
+		rest = chars.as_str();
+	}
+	if seconds == 0 {
+		return Err(CronParseError::SyntaxError(format!("{} - duration must be greater than 0", s), Some(Token::new(s, 0))));
+	}
+	Duration::try_seconds(seconds).ok_or_else(invalid)
+}
+
+impl Display for EveryInterval {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "@every ")?;
+		let mut seconds = self.period.num_seconds();
+		for (unit, scale) in DURATION_UNITS {
+			if seconds >= *scale {
+				write!(f, "{}{}", seconds / scale, unit)?;
+				seconds %= scale;
+			}
+		}
+		if let Some(anchor) = self.anchor {
+			write!(f, " @anchor({})", anchor.to_rfc3339())?;
+		}
+		if self.startup {
+			write!(f, " @startup")?;
+		}
+		if self.catchup != CatchUp::None {
+			write!(f, " @catchup({})", self.catchup)?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,113 @@
+use chrono::{DateTime, Datelike, Duration, DurationRound, LocalResult, Months, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
+
+use super::CronInterval;
+
+impl CronInterval {
+	/// The next instant the interval fires strictly after `after`, following my_timers' DST policy:
+	/// - Wall clock times skipped by a forward transition (i.e 02:30 when clocks jump from 02:00 to 03:00)
+	///   run once, at the first minute (or second) after the transition.
+	/// - Wall clock times repeated by a backward transition (i.e 01:30 when clocks fall back from 02:00 to 01:00)
+	///   only run on their first occurrence.
+	///   Periodic intervals, which have a wildcard minute or hour (i.e `*/5 * * * *`),
+	///   are exempt and keep running in real time through the repeated hour, matching vixie cron.
+	///
+	/// Returns None for @reboot intervals and intervals that never match (i.e `0 0 31 2 *`).
+	pub fn next_after<T: TimeZone>(&self, after: &DateTime<T>) -> Option<DateTime<Utc>> {
+		if self.reboot {
+			return None;
+		}
+		let step = self.resolution();
+		// Search from the start of the minute (or second) following `after`
+		let mut start = after.with_timezone(&Utc).duration_trunc(step).ok()? + step;
+		let mut from = self.local_time(&start);
+		// The search may start at a forward transition, where skipped wall clock times run
+		let before = self.local_time(&(start - step)) + step;
+		if before < from && self.next_local(before).is_some_and(|n| n < from) {
+			return Some(start);
+		}
+		// Within repeated wall clock times, non-periodic intervals continue from the end of the repeat
+		if let LocalResult::Ambiguous(earliest, _) = self.instant_of(&from) {
+			if earliest < start && !self.is_periodic() {
+				let offset = self.local_time(&earliest) - earliest.naive_utc();
+				let transition = self.transition(earliest, start, offset);
+				from = self.local_time(&(transition - step)) + step;
+			}
+		}
+		loop {
+			let local = self.next_local(from)?;
+			// Wall clock time is monotonic while the UTC offset stays the same
+			let offset = self.local_time(&start) - start.naive_utc();
+			let t = Utc.from_utc_datetime(&(local - offset));
+			if self.local_time(&t) == local {
+				return Some(t);
+			}
+
+			// The UTC offset changes before the candidate, continue searching from the transition
+			let transition = self.transition(start, t, offset);
+			let before = self.local_time(&(transition - step)) + step;
+			let after_transition = self.local_time(&transition);
+			if after_transition > before {
+				// Clocks moved forward, skipped wall clock times run at the transition
+				if self.next_local(before).is_some_and(|n| n < after_transition) {
+					return Some(transition);
+				}
+				from = after_transition;
+			} else {
+				// Clocks moved back, only periodic intervals run during repeated wall clock times
+				from = if self.is_periodic() { after_transition } else { before };
+			}
+			start = transition;
+		}
+	}
+
+	/// The first step in (start, end] where the interval's UTC offset differs from `offset`
+	fn transition(&self, start: DateTime<Utc>, end: DateTime<Utc>, offset: Duration) -> DateTime<Utc> {
+		let step = self.resolution();
+		let (mut lo, mut hi) = (0, (end - start).num_seconds() / step.num_seconds());
+		while hi - lo > 1 {
+			let mid = (lo + hi) / 2;
+			let t = start + step * mid as i32;
+			if self.local_time(&t) - t.naive_utc() == offset {
+				lo = mid;
+			} else {
+				hi = mid;
+			}
+		}
+		start + step * hi as i32
+	}
+
+	/// The earliest wall clock time >= from (at the interval's resolution) that matches the interval, ignoring DST
+	fn next_local(&self, from: NaiveDateTime) -> Option<NaiveDateTime> {
+		// Give up on intervals that never match, such as `0 0 31 2 *`.
+		// 28 years covers every combination of leap year and weekday.
+		let limit = from.date().checked_add_months(Months::new(28 * 12))?;
+		let mut date = from.date();
+		let mut from_time = Some((from.hour(), from.minute(), from.second()));
+		while date <= limit {
+			if !self.month.compare(date.month(), Self::month_range()) {
+				// Skip to the first day of the next month
+				date = NaiveDate::from_ymd_opt(date.year(), date.month(), 1)?.checked_add_months(Months::new(1))?;
+				from_time = None;
+				continue;
+			}
+			if self.match_date(date) {
+				let (from_hour, from_minute, from_second) = from_time.unwrap_or((0, 0, 0));
+				let mut hour = from_hour;
+				while let Some(h) = self.hour.next_value(hour, Self::hour_range()) {
+					let mut minute = if h == from_hour { from_minute } else { 0 };
+					while let Some(m) = self.minute.next_value(minute, Self::minute_range()) {
+						let s = if h == from_hour && m == from_minute { from_second } else { 0 };
+						if let Some(s) = self.second().next_value(s, Self::second_range()) {
+							return date.and_hms_opt(h, m, s);
+						}
+						minute = m + 1;
+					}
+					hour = h + 1;
+				}
+			}
+			date = date.succ_opt()?;
+			from_time = None;
+		}
+		None
+	}
+}
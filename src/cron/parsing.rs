@@ -1,27 +1,87 @@
 use std::collections::VecDeque;
 use std::str::FromStr;
 
-use super::error::CronParseError;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use super::error::{CronParseError, Token};
 use super::CronValue;
 use super::CronInterval;
+use super::CatchUp;
+use super::Schedule;
 
 #[derive(Debug, Clone, Copy)]
-pub struct CronRange (u32, u32);
+pub struct CronRange (pub(super) u32, pub(super) u32);
+
+/// Case-insensitive names accepted in place of numbers in a cron field
+type CronNames = &'static [(&'static str, u32)];
+
+const MONTH_NAMES: CronNames = &[
+	("JAN", 1), ("FEB", 2), ("MAR", 3), ("APR", 4), ("MAY", 5), ("JUN", 6),
+	("JUL", 7), ("AUG", 8), ("SEP", 9), ("OCT", 10), ("NOV", 11), ("DEC", 12)
+];
+const WEEKDAY_NAMES: CronNames = &[
+	("SUN", 0), ("MON", 1), ("TUE", 2), ("WED", 3), ("THU", 4), ("FRI", 5), ("SAT", 6)
+];
+
+/// A field of a cron expression
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CronField {
+	Second,
+	Minute,
+	Hour,
+	Day,
+	Month,
+	Weekday
+}
+
+impl CronField {
+	const fn range(&self) -> CronRange {
+		match self {
+			Self::Second => CronInterval::second_range(),
+			Self::Minute => CronInterval::minute_range(),
+			Self::Hour => CronInterval::hour_range(),
+			Self::Day => CronInterval::day_range(),
+			Self::Month => CronInterval::month_range(),
+			Self::Weekday => CronInterval::weekday_range()
+		}
+	}
+
+	/// Range H picks a value from, avoiding days that don't occur in every month and Sunday's duplicate 7
+	const fn hash_range(&self) -> CronRange {
+		match self {
+			Self::Day => CronRange(1, 28),
+			Self::Weekday => CronRange(0, 6),
+			_ => self.range()
+		}
+	}
+
+	const fn names(&self) -> CronNames {
+		match self {
+			Self::Month => MONTH_NAMES,
+			Self::Weekday => WEEKDAY_NAMES,
+			_ => &[]
+		}
+	}
+}
 
 impl CronInterval {
-	const fn minute_range() -> CronRange {
+	pub(super) const fn second_range() -> CronRange {
+		CronRange(0, 59)
+	}
+	pub(super) const fn minute_range() -> CronRange {
 		CronRange(0, 59)
 	}
-	const fn hour_range() -> CronRange {
+	pub(super) const fn hour_range() -> CronRange {
 		CronRange(0, 23)
 	}
-	const fn day_range() -> CronRange {
+	pub(super) const fn day_range() -> CronRange {
 		CronRange(1, 31)
 	}
-	const fn month_range() -> CronRange {
+	pub(super) const fn month_range() -> CronRange {
 		CronRange(1, 12)
 	}
-	const fn weekday_range() -> CronRange {
+	pub(super) const fn weekday_range() -> CronRange {
 		CronRange(0, 7)
 	}
 }
@@ -31,7 +91,7 @@ impl CronRange {
 		// Use closure for validating against range values
 		let validate = |n: &u32| -> Result<(), CronParseError> {
 			if n > &self.1 || n < &self.0 {
-				Err(CronParseError::OutOfRange(Box::new(v.clone()), Box::new(self.clone())))
+				Err(CronParseError::OutOfRange(Box::new(v.clone()), Box::new(*self), None))
 			} else {
 				Ok(())
 			}
@@ -40,85 +100,374 @@ impl CronRange {
 		match v {
 			CronValue::Every => Ok(()),
 			CronValue::Value(n) => validate(n),
-			CronValue::Range((n1, n2)) => validate(n1).and_then(|_| validate(n2)),
+			CronValue::Range((n1, n2)) => {
+				// Ranges don't wrap around, so one running backwards would never match
+				if n1 > n2 {
+					return Err(CronParseError::SyntaxError(format!("{} - range start must not exceed its end", v), None));
+				}
+				validate(n1).and_then(|_| validate(n2))
+			},
 			CronValue::Set(s) => {
 				for n in s {
-					if let Err(e) = validate(n) {
-						return Err(e);
-					}
+					validate(n)?;
 				}
 				Ok(())
+			},
+			CronValue::List(l) => {
+				for v in l {
+					self.validate(v)?;
+				}
+				Ok(())
+			},
+			CronValue::Step(start, step) => {
+				if *step == 0 {
+					return Err(CronParseError::SyntaxError(format!("{} - step must be greater than 0", v), None));
+				}
+				self.validate(start)
+			},
+			CronValue::LastDay | CronValue::Hash(None) => Ok(()),
+			CronValue::Hash(Some((n1, n2))) => {
+				if n1 > n2 {
+					return Err(CronParseError::SyntaxError(format!("{} - range start must not exceed its end", v), None));
+				}
+				validate(n1).and_then(|_| validate(n2))
+			},
+			CronValue::NearestWeekday(n) | CronValue::LastWeekday(n) => validate(n),
+			CronValue::NthWeekday((d, n)) => {
+				if !(1..=5).contains(n) {
+					return Err(CronParseError::SyntaxError(format!("{} - occurrence must be between 1 and 5", v), None));
+				}
+				validate(d)
 			}
 		}
 	}
 }
 
+impl FromStr for Schedule {
+	type Err = CronParseError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.split_whitespace().next() {
+			Some("@every") => Ok(Self::Every(s.parse()?)),
+			Some("@at") => Ok(Self::At(s.parse()?)),
+			_ => Ok(Self::Cron(s.parse()?))
+		}
+	}
+}
+
 impl FromStr for CronInterval {
 	type Err = CronParseError;
 
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
-		// To start parsing off simple, it's good to make sure the right amount of cron values are present
+		let mut values = tokens(s);
+		// Check for a timezone prefix (i.e CRON_TZ=Europe/Berlin)
+		let tz = match values.front().and_then(|(offset, v)| Some((offset, v.strip_prefix("CRON_TZ=")?))) {
+			Some((offset, name)) => {
+				let tz = parse_tz(name).map_err(|e| e.offset(offset + 8))?;
+				values.pop_front();
+				Some(tz)
+			},
+			None => None
+		};
+		// Macros replace the entire cron expression
+		let mut interval = match values.front().copied() {
+			Some((offset, m)) if m.starts_with('@') && m != "@startup" => {
+				let interval = Self::from_macro(m).map_err(|e| e.or_token(m, offset))?;
+				values.pop_front();
+				interval
+			},
+			_ => Self::from_fields(&mut values)?
+		};
+		interval.tz = tz;
+		interval.parse_tags(values)?;
+		Ok(interval)
+	}
+}
+
+impl CronInterval {
+	/// Parse the fields of a cron expression, with an optional leading seconds field
+	fn from_fields(values: &mut VecDeque<(usize, &str)>) -> Result<Self, CronParseError> {
+		// To start parsing off simple, it's good to make sure the right amount of cron values are present.
+		// Every value before the first tag is a field.
 		const CRON_LEN: usize = 5;
-		let mut values: VecDeque<&str> = s.splitn(CRON_LEN + 1, ' ').collect();
-		if values.len() < CRON_LEN {
-			return Err(Self::Err::SyntaxError(
-				format!("{} - unexpected number of cron values (expected {}, received {})", s, CRON_LEN, values.len())
+		const CRON_LEN_SECONDS: usize = 6;
+		let n_fields = values.iter().take_while(|(_, v)| !v.starts_with('@')).count();
+		if n_fields != CRON_LEN && n_fields != CRON_LEN_SECONDS {
+			let values: Vec<&str> = values.iter().map(|(_, v)| *v).collect();
+			return Err(CronParseError::SyntaxError(
+				format!("{} - unexpected number of cron values (expected {} or {}, received {})",
+					values.join(" "), CRON_LEN, CRON_LEN_SECONDS, n_fields),
+				None
 			));
 		}
 		// Next, parse and validate each value according to its expected range
 		macro_rules! next {
-			($range:path) => {
+			($field:expr) => {
 				{
-					let v: CronValue = values.pop_front().unwrap().parse()?;
-					$range().validate(&v)?;
+					let (offset, s) = values.pop_front().unwrap();
+					let v = CronValue::parse_field(s, $field).map_err(|e| e.offset(offset))?;
+					$field.range().validate(&v).map_err(|e| e.or_token(s, offset))?;
 					v
 				}
 			};
 		}
-		let interval = CronInterval { // Each value is moved to the interval struct
-			minute: next!(Self::minute_range),
-			hour: next!(Self::hour_range),
-			day: next!(Self::day_range),
-			month: next!(Self::month_range),
-			weekday: next!(Self::weekday_range),
-			// Check for an @startup tag at the end
-			startup: values.pop_front() == Some("@startup")
+		Ok(CronInterval { // Each value is moved to the interval struct
+			second: if n_fields == CRON_LEN_SECONDS { Some(next!(CronField::Second)) } else { None },
+			minute: next!(CronField::Minute),
+			hour: next!(CronField::Hour),
+			day: next!(CronField::Day),
+			month: next!(CronField::Month),
+			weekday: next!(CronField::Weekday),
+			startup: false,
+			reboot: false,
+			tz: None,
+			catchup: CatchUp::None
+		})
+	}
+
+	/// Expand a cron macro (i.e @daily) to its equivalent interval
+	fn from_macro(m: &str) -> Result<Self, CronParseError> {
+		let expanded = match m {
+			"@yearly" | "@annually" => "0 0 1 1 *",
+			"@monthly" => "0 0 1 * *",
+			"@weekly" => "0 0 * * 0",
+			"@daily" | "@midnight" => "0 0 * * *",
+			"@hourly" => "0 * * * *",
+			"@reboot" => {
+				// Startup-only interval that never matches a minute tick
+				return Ok(Self {
+					second: None,
+					minute: CronValue::Every,
+					hour: CronValue::Every,
+					day: CronValue::Every,
+					month: CronValue::Every,
+					weekday: CronValue::Every,
+					startup: true,
+					reboot: true,
+					tz: None,
+					catchup: CatchUp::None
+				});
+			},
+			_ => return Err(CronParseError::SyntaxError(format!("unknown macro {}", m), None))
 		};
-		Ok(interval)
+		Self::from_fields(&mut tokens(expanded))
+	}
+
+	/// Parse the tags following a cron expression (i.e @startup)
+	fn parse_tags(&mut self, tags: VecDeque<(usize, &str)>) -> Result<(), CronParseError> {
+		for (offset, tag) in tags {
+			match tag {
+				"@startup" => self.startup = true,
+				_ if tag.starts_with("@tz(") && tag.ends_with(')') => {
+					if self.tz.is_some() {
+						return Err(CronParseError::SyntaxError(format!("{} - timezone is already set", tag), Some(Token::new(tag, offset))));
+					}
+					self.tz = Some(parse_tz(&tag[4..tag.len()-1]).map_err(|e| e.offset(offset + 4))?);
+				},
+				_ if tag.starts_with("@catchup(") && tag.ends_with(')') => {
+					self.catchup = tag[9..tag.len()-1].parse().map_err(|e: CronParseError| e.offset(offset + 9))?;
+				},
+				_ => return Err(CronParseError::SyntaxError(format!("unexpected token {}", tag), Some(Token::new(tag, offset))))
+			}
+		}
+		Ok(())
+	}
+}
+
+impl FromStr for CatchUp {
+	type Err = CronParseError;
+
+	/// Parse a catch-up policy: none, once, or all,<limit>
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.split_once(',') {
+			None if s == "none" => Ok(Self::None),
+			None if s == "once" => Ok(Self::Once),
+			Some(("all", limit)) => {
+				// The limit follows "all,"
+				let token = || Token::new(limit, 4);
+				let n: u32 = limit.parse().map_err(|e| CronParseError::ParseIntError(token(), e))?;
+				if n == 0 {
+					return Err(CronParseError::SyntaxError("catch-up limit must be greater than 0".into(), Some(token())));
+				}
+				Ok(Self::All(n))
+			},
+			_ => Err(CronParseError::SyntaxError(format!("invalid catch-up policy {} (expected none, once, or all,<limit>)", s), Some(Token::new(s, 0))))
+		}
+	}
+}
+
+impl CronInterval {
+	/// Replace H values with values derived from a hash of the event's label (Jenkins-style),
+	/// spreading intervals such as `H * * * *` across events while keeping each event's schedule stable across restarts
+	pub fn resolve_hashes(&mut self, label: &str) {
+		if let Some(second) = &mut self.second {
+			second.resolve_hash(label, CronField::Second);
+		}
+		self.minute.resolve_hash(label, CronField::Minute);
+		self.hour.resolve_hash(label, CronField::Hour);
+		self.day.resolve_hash(label, CronField::Day);
+		self.month.resolve_hash(label, CronField::Month);
+		self.weekday.resolve_hash(label, CronField::Weekday);
+	}
+
+	/// Whether any field has an H value that hasn't been resolved
+	pub fn has_hashes(&self) -> bool {
+		[Some(&self.minute), Some(&self.hour), Some(&self.day), Some(&self.month), Some(&self.weekday), self.second.as_ref()]
+			.into_iter()
+			.flatten()
+			.any(CronValue::has_hash)
+	}
+}
+
+impl CronValue {
+	/// Replace H values in a field with values derived from a hash of the event's label
+	fn resolve_hash(&mut self, label: &str, field: CronField) {
+		match self {
+			Self::Hash(range) => {
+				let (start, end) = range.unwrap_or((field.hash_range().0, field.hash_range().1));
+				*self = Self::Value(start + (label_hash(label, field) % u64::from(end - start + 1)) as u32);
+			},
+			Self::Step(start, step) => {
+				// H/n starts at an offset below n, and steps through the rest of the range
+				if let Self::Hash(range) = start.as_ref() {
+					let (first, last) = range.unwrap_or((field.range().0, field.range().1));
+					let offset = (label_hash(label, field) % u64::from((*step).min(last - first + 1))) as u32;
+					**start = match range {
+						Some(_) => Self::Range((first + offset, last)),
+						None => Self::Value(first + offset)
+					};
+				}
+			},
+			Self::List(list) => list.iter_mut().for_each(|v| v.resolve_hash(label, field)),
+			_ => {}
+		}
+	}
+
+	fn has_hash(&self) -> bool {
+		match self {
+			Self::Hash(_) => true,
+			Self::Step(start, _) => start.has_hash(),
+			Self::List(list) => list.iter().any(Self::has_hash),
+			_ => false
+		}
 	}
 }
 
+/// 64-bit FNV-1a hash of an event label and a cron field, which unlike std's hashers is stable across Rust versions.
+/// The result is mixed with MurmurHash3's finalizer, since FNV's low bits are poorly distributed for similar labels.
+fn label_hash(label: &str, field: CronField) -> u64 {
+	const OFFSET: u64 = 0xcbf29ce484222325;
+	const PRIME: u64 = 0x100000001b3;
+	let mut hash = label.bytes().chain([field as u8]).fold(OFFSET, |hash, b| (hash ^ u64::from(b)).wrapping_mul(PRIME));
+	hash ^= hash >> 33;
+	hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+	hash ^= hash >> 33;
+	hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+	hash ^ (hash >> 33)
+}
+
+/// Parse an IANA timezone name (i.e Europe/Berlin)
+pub(super) fn parse_tz(name: &str) -> Result<Tz, CronParseError> {
+	name.parse().map_err(|_| CronParseError::SyntaxError(format!("unknown timezone {}", name), Some(Token::new(name, 0))))
+}
+
+/// Parse an RFC 3339 timestamp, or a date and time without an offset in a timezone
+/// defaulting to the host's local timezone (i.e 2026-11-01T03:00:00Z, 2026-11-01T03:00)
+pub(super) fn parse_datetime(s: &str, tz: Option<Tz>) -> Result<DateTime<Utc>, CronParseError> {
+	if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+		return Ok(t.with_timezone(&Utc));
+	}
+	let local = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S")
+		.or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M"))
+		.map_err(|e| CronParseError::SyntaxError(format!("invalid date and time {}: {}", s, e), Some(Token::new(s, 0))))?;
+	let t = match tz {
+		Some(tz) => tz.from_local_datetime(&local).earliest().map(|t| t.with_timezone(&Utc)),
+		None => Local.from_local_datetime(&local).earliest().map(|t| t.with_timezone(&Utc))
+	};
+	t.ok_or_else(|| CronParseError::SyntaxError(format!("{} does not exist in the interval's timezone", s), Some(Token::new(s, 0))))
+}
+
 impl FromStr for CronValue {
 	type Err = CronParseError;
 
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::parse_field(s, CronField::Minute)
+	}
+}
+
+impl CronValue {
+	/// Parse a cron field, accepting the field's names anywhere a number is allowed,
+	/// and L/W/# in the day of month and weekday fields.
+	/// Errors point at tokens by their offset in the field.
+	fn parse_field(s: &str, field: CronField) -> Result<Self, CronParseError> {
+		// Parse a single number or name, found at `offset` in the field
+		let number = |v: &str, offset: usize| -> Result<u32, CronParseError> {
+			if let Some((_, n)) = field.names().iter().find(|(name, _)| name.eq_ignore_ascii_case(v)) {
+				return Ok(*n);
+			}
+			v.parse().map_err(|e| CronParseError::ParseIntError(Token::new(v, offset), e))
+		};
+
 		if s == "*" { // Parse 'every' value
 			Ok(Self::Every)
-		} else if s.contains(',') { // Parse set
+		} else if s == "H" { // Parse hash
+			Ok(Self::Hash(None))
+		} else if let Some(range) = s.strip_prefix("H(").and_then(|r| r.strip_suffix(')')) { // Parse hash within a range
+			let Some((start, end)) = range.split_once('-') else {
+				return Err(CronParseError::MalformedTokens(format!("Invalid cron hash range {}", s), Token::new(s, 0)));
+			};
+			Ok(Self::Hash(Some((number(start, 2)?, number(end, 2 + start.len() + 1)?))))
+		} else if s.contains(',') { // Parse set or list
 			let s_values: Vec<&str> = s.split(',').collect();
-			let mut values: Vec<u32> = Vec::with_capacity(s_values.len());
+			let mut values: Vec<CronValue> = Vec::with_capacity(s_values.len());
+			let mut offset = 0;
 			for v in s_values {
-				let n: u32 = v.parse().map_err(Self::Err::ParseIntError)?;
-				values.push(n);
+				let parsed = Self::parse_field(v, field).map_err(|e| e.offset(offset))?;
+				offset += v.len() + 1;
+				// Remove duplicate values
+				if !values.contains(&parsed) {
+					values.push(parsed);
+				}
 			}
-			// Remove duplicate values
-			values.sort();
-			values.dedup();
-			Ok(Self::Set(values))
-		} else if s.contains('-') { // Parse range
-			let s_values: Vec<&str> = s.splitn(2, '-').collect();
-			if s_values.len() != 2 {
-				return Err(Self::Err::MalformedTokens("Invalid cron range".into()));
+			// Sets of plain values are kept sorted for binary search,
+			// anything else is kept in the order it was written
+			if values.iter().all(|v| matches!(v, Self::Value(_))) {
+				let mut set: Vec<u32> = values.into_iter().filter_map(|v| match v {
+					Self::Value(n) => Some(n),
+					_ => None
+				}).collect();
+				set.sort();
+				Ok(Self::Set(set))
+			} else {
+				Ok(Self::List(values))
 			}
-			let mut values: Vec<u32> = Vec::with_capacity(s_values.len());
-			for v in s_values {
-				let n: u32 = v.parse().map_err(Self::Err::ParseIntError)?;
-				values.push(n);
+		} else if s.contains('/') { // Parse step
+			let (start, step) = s.split_once('/').unwrap();
+			let start_value = Self::parse_field(start, field)?;
+			if !matches!(start_value, Self::Every | Self::Value(_) | Self::Range(_) | Self::Hash(_)) {
+				return Err(CronParseError::MalformedTokens(format!("Invalid cron step {}", s), Token::new(s, 0)));
 			}
-			Ok(Self::Range((values[0], values[1])))
+			let step: u32 = step.parse().map_err(|e| CronParseError::ParseIntError(Token::new(step, start.len() + 1), e))?;
+			Ok(Self::Step(Box::new(start_value), step))
+		} else if field == CronField::Weekday && s.contains('#') { // Parse nth weekday
+			let (weekday, n) = s.split_once('#').unwrap();
+			let n: u32 = n.parse().map_err(|e| CronParseError::ParseIntError(Token::new(n, weekday.len() + 1), e))?;
+			Ok(Self::NthWeekday((number(weekday, 0)?, n)))
+		} else if field == CronField::Day && s.eq_ignore_ascii_case("L") { // Parse last day of month
+			Ok(Self::LastDay)
+		} else if let Some(n) = s.strip_suffix(['W', 'w']).filter(|_| field == CronField::Day) { // Parse nearest weekday
+			Ok(Self::NearestWeekday(number(n, 0)?))
+		} else if let Some(d) = s.strip_suffix(['L', 'l']).filter(|_| field == CronField::Weekday) { // Parse last weekday
+			Ok(Self::LastWeekday(number(d, 0)?))
+		} else if let Some((start, end)) = s.split_once('-') { // Parse range
+			Ok(Self::Range((number(start, 0)?, number(end, start.len() + 1)?)))
 		} else { // Parse individual value
-			let n: u32 = s.parse().map_err(Self::Err::ParseIntError)?;
-			Ok(Self::Value(n))
+			Ok(Self::Value(number(s, 0)?))
 		}
 	}
 }
+
+/// Whitespace-separated tokens, with their byte offset
+pub fn tokens(s: &str) -> VecDeque<(usize, &str)> {
+	s.split_whitespace().map(|token| (token.as_ptr() as usize - s.as_ptr() as usize, token)).collect()
+}
@@ -0,0 +1,487 @@
+use chrono::{DateTime, Duration, FixedOffset, Local, TimeZone, Utc};
+
+use super::{CronInterval, Schedule};
+use super::every::EveryInterval;
+use crate::test_util::tree;
+
+/// (year, month, day, hour, minute)
+type Time = (i32, u32, u32, u32, u32);
+
+/// Build a local timestamp from a Time tuple
+fn local(t: Time) -> DateTime<Local> {
+	Local.with_ymd_and_hms(t.0, t.1, t.2, t.3, t.4, 0).single().unwrap()
+}
+
+#[test]
+fn match_time() {
+	// (interval, time, expected match)
+	// 2024-06-03 is a Monday, 2024-06-01 is a Saturday
+	let cases: &[(&str, Time, bool)] = &[
+		// Minute and hour
+		("* * * * *", (2024, 6, 3, 12, 0), true),
+		("30 * * * *", (2024, 6, 3, 12, 30), true),
+		("30 * * * *", (2024, 6, 3, 12, 31), false),
+		("*/15 9-17 * * *", (2024, 6, 3, 9, 45), true),
+		("*/15 9-17 * * *", (2024, 6, 3, 18, 0), false),
+		// Month
+		("0 0 1 6 *", (2024, 6, 1, 0, 0), true),
+		("0 0 1 6 *", (2024, 7, 1, 0, 0), false),
+		("0 0 1 1,4,7,10 *", (2024, 7, 1, 0, 0), true),
+		("0 0 1 1,4,7,10 *", (2024, 6, 1, 0, 0), false),
+		("0 0 * JUN *", (2024, 6, 15, 0, 0), true),
+		("0 0 * JUN *", (2024, 5, 15, 0, 0), false),
+		("0 0 * 6 MON", (2024, 6, 3, 0, 0), true),
+		("0 0 * 6 MON", (2024, 7, 1, 0, 0), false),
+		("0 0 1 6 MON", (2024, 7, 1, 0, 0), false),
+		// Day of month only (weekday is *): intersection
+		("0 0 1 * *", (2024, 6, 1, 0, 0), true),
+		("0 0 1 * *", (2024, 6, 3, 0, 0), false),
+		// Weekday only (day of month is *): intersection
+		("0 0 * * 1", (2024, 6, 3, 0, 0), true),
+		("0 0 * * 1", (2024, 6, 1, 0, 0), false),
+		("0 0 */2 * 1", (2024, 6, 3, 0, 0), true),
+		("0 0 */2 * 1", (2024, 6, 10, 0, 0), false),
+		// Sunday is both 0 and 7
+		("0 0 * * 0", (2024, 6, 2, 0, 0), true),
+		("0 0 * * 7", (2024, 6, 2, 0, 0), true),
+		("0 0 * * SUN", (2024, 6, 2, 0, 0), true),
+		// Both day of month and weekday set: union
+		("0 0 1 * 1", (2024, 6, 1, 0, 0), true),
+		("0 0 1 * 1", (2024, 6, 3, 0, 0), true),
+		("0 0 1 * 1", (2024, 6, 4, 0, 0), false),
+		("0 0 15 * MON-FRI", (2024, 6, 15, 0, 0), true),
+		("0 0 15 * MON-FRI", (2024, 6, 16, 0, 0), false),
+		// Last day of month
+		("0 0 L * *", (2024, 2, 29, 0, 0), true),
+		("0 0 L * *", (2023, 2, 28, 0, 0), true),
+		("0 0 L * *", (2024, 2, 28, 0, 0), false),
+		("0 0 L * *", (2024, 12, 31, 0, 0), true),
+		("0 0 1,L * *", (2024, 4, 30, 0, 0), true),
+		// Nearest weekday (2024-06-15 is a Saturday, 2024-06-01 is a Saturday, 2024-06-30 is a Sunday)
+		("0 0 15W * *", (2024, 6, 14, 0, 0), true),
+		("0 0 15W * *", (2024, 6, 15, 0, 0), false),
+		("0 0 15W * *", (2024, 7, 15, 0, 0), true),
+		("0 0 1W * *", (2024, 6, 3, 0, 0), true),
+		("0 0 30W * *", (2024, 6, 28, 0, 0), true),
+		("0 0 31W * *", (2024, 6, 28, 0, 0), false),
+		// Nth weekday (2024-06-11 is the second Tuesday)
+		("0 0 * * 2#2", (2024, 6, 11, 0, 0), true),
+		("0 0 * * TUE#2", (2024, 6, 11, 0, 0), true),
+		("0 0 * * 2#2", (2024, 6, 4, 0, 0), false),
+		("0 0 * * 2#2", (2024, 6, 12, 0, 0), false),
+		("0 0 * * 7#1", (2024, 6, 2, 0, 0), true),
+		// Last weekday (2024-06-28 is the last Friday)
+		("0 0 * * 5L", (2024, 6, 28, 0, 0), true),
+		("0 0 * * FRIL", (2024, 6, 21, 0, 0), false),
+		// Macros
+		("@daily", (2024, 6, 3, 0, 0), true),
+		("@daily", (2024, 6, 3, 0, 1), false),
+		("@yearly", (2024, 1, 1, 0, 0), true),
+		("@yearly", (2024, 6, 1, 0, 0), false),
+		("@reboot", (2024, 6, 3, 0, 0), false),
+	];
+	for (interval, time, expected) in cases {
+		let parsed: CronInterval = interval.parse().unwrap();
+		assert_eq!(parsed.match_time(&local(*time)), *expected, "{} at {:?}", interval, time);
+	}
+}
+
+#[test]
+fn match_time_tz() {
+	// (interval, UTC time, expected match)
+	let cases: &[(&str, Time, bool)] = &[
+		("CRON_TZ=Europe/Berlin 0 0 * * *", (2024, 6, 2, 22, 0), true), // CEST, UTC+2
+		("CRON_TZ=Europe/Berlin 0 0 * * *", (2024, 12, 2, 23, 0), true), // CET, UTC+1
+		("CRON_TZ=Europe/Berlin 0 0 * * *", (2024, 6, 3, 0, 0), false),
+		("0 9 * * * @tz(America/New_York)", (2024, 6, 3, 13, 0), true), // EDT, UTC-4
+		("0 9 * * * @tz(America/New_York)", (2024, 6, 3, 9, 0), false),
+		// Weekdays and days follow the interval's timezone
+		("CRON_TZ=Asia/Tokyo 0 8 * * MON", (2024, 6, 2, 23, 0), true), // Sunday in UTC, Monday in Tokyo
+		("CRON_TZ=Asia/Tokyo 0 8 1 * *", (2024, 5, 31, 23, 0), true),
+	];
+	for (interval, t, expected) in cases {
+		let parsed: CronInterval = interval.parse().unwrap();
+		let now = Utc.with_ymd_and_hms(t.0, t.1, t.2, t.3, t.4, 0).unwrap();
+		assert_eq!(parsed.match_time(&now), *expected, "{} at {:?} UTC", interval, t);
+	}
+}
+
+/// Build a timestamp at a fixed UTC offset (in hours) from a Time tuple
+fn fixed(t: Time, offset: i32) -> DateTime<FixedOffset> {
+	FixedOffset::east_opt(offset * 3600).unwrap()
+		.with_ymd_and_hms(t.0, t.1, t.2, t.3, t.4, 0).unwrap()
+}
+
+#[test]
+fn next_after_dst() {
+	// (interval, time, UTC offset of the time, expected to fire at the time)
+	// Europe/Berlin springs forward from 02:00 CET (+1) to 03:00 CEST (+2) on 2024-03-31,
+	// and falls back from 03:00 CEST (+2) to 02:00 CET (+1) on 2024-10-27
+	const BERLIN: &str = "CRON_TZ=Europe/Berlin";
+	let cases: &[(&str, Time, i32, bool)] = &[
+		// Skipped times run once at the transition
+		("30 2 * * *", (2024, 3, 31, 3, 0), 2, true),
+		("30 2 * * *", (2024, 3, 31, 3, 1), 2, false),
+		("30 2 * * *", (2024, 3, 31, 1, 59), 1, false),
+		("59 2 * * *", (2024, 3, 31, 3, 0), 2, true),
+		("0 3 * * *", (2024, 3, 31, 3, 0), 2, true),
+		("30 2 * * *", (2024, 4, 1, 2, 30), 2, true),
+		("30 2 1 * *", (2024, 3, 31, 3, 0), 2, false),
+		// Repeated times only run on their first occurrence
+		("30 2 * * *", (2024, 10, 27, 2, 30), 2, true),
+		("30 2 * * *", (2024, 10, 27, 2, 30), 1, false),
+		("0 2 * * *", (2024, 10, 27, 2, 0), 2, true),
+		("0 2 * * *", (2024, 10, 27, 2, 0), 1, false),
+		("0 3 * * *", (2024, 10, 27, 3, 0), 1, true),
+		// Periodic intervals keep running in real time
+		("* * * * *", (2024, 10, 27, 2, 30), 1, true),
+		("*/15 * * * *", (2024, 10, 27, 2, 30), 1, true),
+		("0 * * * *", (2024, 10, 27, 2, 0), 1, true),
+		("* * * * *", (2024, 3, 31, 3, 0), 2, true),
+	];
+	for (interval, t, offset, expected) in cases {
+		let parsed: CronInterval = format!("{} {}", BERLIN, interval).parse().unwrap();
+		let t = fixed(*t, *offset).with_timezone(&Utc);
+		let fires = parsed.next_after(&(t - Duration::minutes(1))) == Some(t);
+		assert_eq!(fires, *expected, "{} at {}", interval, t);
+	}
+}
+
+#[test]
+fn next_after() {
+	// (interval, after, expected next run), all times in UTC
+	let cases: &[(&str, Time, Option<Time>)] = &[
+		("* * * * *", (2024, 6, 3, 12, 0), Some((2024, 6, 3, 12, 1))),
+		("*/15 * * * *", (2024, 6, 3, 12, 15), Some((2024, 6, 3, 12, 30))),
+		("0 0 * * *", (2024, 12, 31, 23, 59), Some((2025, 1, 1, 0, 0))),
+		("30 9 * * MON-FRI", (2024, 6, 7, 10, 0), Some((2024, 6, 10, 9, 30))), // Friday -> Monday
+		("0 0 29 2 *", (2024, 3, 1, 0, 0), Some((2028, 2, 29, 0, 0))),
+		("0 0 L * *", (2024, 2, 1, 0, 0), Some((2024, 2, 29, 0, 0))),
+		("0 0 1 * 1", (2024, 6, 1, 0, 0), Some((2024, 6, 3, 0, 0))), // Union of day and weekday
+		("0 12 * * 2#2", (2024, 6, 1, 0, 0), Some((2024, 6, 11, 12, 0))),
+		("0 0 31 2 *", (2024, 1, 1, 0, 0), None),
+		// Seconds field
+		("0 * * * * *", (2024, 6, 3, 12, 0), Some((2024, 6, 3, 12, 1))),
+		("0 0 0 * * *", (2024, 6, 3, 12, 0), Some((2024, 6, 4, 0, 0))),
+		("@reboot", (2024, 1, 1, 0, 0), None),
+	];
+	for (interval, after, expected) in cases {
+		let parsed: CronInterval = format!("CRON_TZ=UTC {}", interval).parse().unwrap();
+		let after = Utc.with_ymd_and_hms(after.0, after.1, after.2, after.3, after.4, 0).unwrap();
+		let expected = expected.map(|t| Utc.with_ymd_and_hms(t.0, t.1, t.2, t.3, t.4, 0).unwrap());
+		assert_eq!(parsed.next_after(&after), expected, "{} after {}", interval, after);
+	}
+}
+
+fn upcoming(interval: &CronInterval, after: DateTime<Utc>) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+	std::iter::successors(interval.next_after(&after), |last| interval.next_after(last))
+}
+
+#[test]
+fn next_after_seconds() {
+	let every_10s: CronInterval = "CRON_TZ=UTC */10 * * * * *".parse().unwrap();
+	let after = Utc.with_ymd_and_hms(2024, 6, 3, 12, 0, 5).unwrap();
+	let runs: Vec<_> = upcoming(&every_10s, after).take(6).map(|t| t.format("%H:%M:%S").to_string()).collect();
+	assert_eq!(runs, ["12:00:10", "12:00:20", "12:00:30", "12:00:40", "12:00:50", "12:01:00"]);
+
+	let at_30s: CronInterval = "CRON_TZ=UTC 30 0 12 * * *".parse().unwrap();
+	let after = Utc.with_ymd_and_hms(2024, 6, 3, 12, 0, 30).unwrap();
+	assert_eq!(at_30s.next_after(&after), Some(Utc.with_ymd_and_hms(2024, 6, 4, 12, 0, 30).unwrap()));
+
+	// Five field intervals keep firing at 0 seconds
+	let every_minute: CronInterval = "CRON_TZ=UTC * * * * *".parse().unwrap();
+	let after = Utc.with_ymd_and_hms(2024, 6, 3, 12, 0, 5).unwrap();
+	assert_eq!(every_minute.next_after(&after), Some(Utc.with_ymd_and_hms(2024, 6, 3, 12, 1, 0).unwrap()));
+	assert!(every_minute.match_time(&after));
+
+	// Seconds across DST transitions: the skipped 02:30 runs once at the transition,
+	// and the repeated 02:30 only runs in its first occurrence (CEST, UTC+2)
+	let berlin: CronInterval = "CRON_TZ=Europe/Berlin */15 30 2 * * *".parse().unwrap();
+	let cases = [
+		(Utc.with_ymd_and_hms(2024, 3, 31, 0, 0, 0).unwrap(), vec!["01:00:00"]),
+		(Utc.with_ymd_and_hms(2024, 10, 27, 0, 0, 0).unwrap(), vec!["00:30:00", "00:30:15", "00:30:30", "00:30:45"])
+	];
+	for (start, expected) in cases {
+		let end = start + Duration::hours(3);
+		let runs: Vec<_> = upcoming(&berlin, start).take_while(|t| t <= &end).map(|t| t.format("%H:%M:%S").to_string()).collect();
+		assert_eq!(runs, expected, "from {}", start);
+	}
+}
+
+#[test]
+fn next_after_resumes() {
+	// Resuming from any minute, including across DST transitions, must continue the same sequence of fire times
+	let intervals = [
+		"* * * * *", "*/7 * * * *", "30 2 * * *", "0 3 * * *", "59 1 * * *", "15 2,3 * * *",
+		"*/20 2 * * *", "0 * * * *", "0 0 * * *", "30 1 * * *"
+	];
+	let zones = ["Europe/Berlin", "America/New_York", "Australia/Lord_Howe", "UTC"];
+	let periods = [
+		Utc.with_ymd_and_hms(2024, 3, 9, 12, 0, 0).unwrap(), // Spring forward (America/New_York)
+		Utc.with_ymd_and_hms(2024, 3, 30, 12, 0, 0).unwrap(), // Spring forward (Europe/Berlin)
+		Utc.with_ymd_and_hms(2024, 4, 6, 6, 0, 0).unwrap(), // Fall back by 30 minutes (Australia/Lord_Howe)
+		Utc.with_ymd_and_hms(2024, 10, 26, 12, 0, 0).unwrap(), // Fall back (Europe/Berlin)
+		Utc.with_ymd_and_hms(2024, 11, 2, 12, 0, 0).unwrap(), // Fall back (America/New_York)
+	];
+	for zone in zones {
+		for interval in intervals {
+			let parsed: CronInterval = format!("CRON_TZ={} {}", zone, interval).parse().unwrap();
+			for start in periods {
+				let end = start + Duration::hours(48);
+				let runs: Vec<_> = upcoming(&parsed, start).take_while(|t| t <= &end).collect();
+				assert!(runs.windows(2).all(|w| w[0] < w[1]), "{} {} from {}", zone, interval, start);
+				let mut t = start;
+				while t < end {
+					let expected = runs.iter().find(|r| **r > t).copied();
+					let next = parsed.next_after(&t).filter(|n| *n <= end);
+					assert_eq!(next, expected, "{} {} after {}", zone, interval, t);
+					t += Duration::minutes(1);
+				}
+			}
+		}
+	}
+}
+
+#[test]
+fn parse_errors() {
+	let cases = [
+		"0 0 L * L", // L in the weekday field needs a weekday
+		"L 0 * * *", // L outside of the day fields
+		"0 0 1#2 * *", // # outside of the weekday field
+		"0 0 * * 2W", // W outside of the day of month field
+		"0 0 32W * *",
+		"0 0 * * 2#6",
+		"0 0 L/2 * *",
+		"CRON_TZ=Mars/Olympus_Mons 0 0 * * *",
+		"CRON_TZ=UTC 0 0 * * * @tz(UTC)",
+		"0 0 * * * @catchup(always)",
+		"0 0 * * * @catchup(all,0)",
+		"0 0 * * * @catchup(all)",
+		"* * * *",
+		"0 * * * * * *",
+		"60 * * * * *",
+		"0 0 * * FRI-SUN", // Ranges running backwards
+		"0 0 * * 5-0",
+		"30-10 * * * *",
+		"0 0 * DEC-JAN *",
+		"*/5 20-4 * * *"
+	];
+	for interval in cases {
+		assert!(interval.parse::<CronInterval>().is_err(), "{}", interval);
+	}
+}
+
+#[test]
+fn error_tokens() {
+	// (interval, token the error points at, its offset in the interval)
+	let cases = [
+		("60 * * * *", "60", 0),
+		("0 0 * * 5#6", "5#6", 8),
+		("5 5 * * 5#x", "x", 10), // The same text earlier in the interval isn't mistaken for the token
+		("0 0 1,5,x * *", "x", 8),
+		("*/5 20-4 * * *", "20-4", 4),
+		("0 H(0-x) * * *", "x", 6),
+		("0 0 * * MON-FOO", "FOO", 12),
+		("0 0 L/2 * *", "L/2", 4),
+		("*/x * * * *", "x", 2),
+		("CRON_TZ=Mars/Olympus_Mons 0 0 * * *", "Mars/Olympus_Mons", 8),
+		("0 0 * * * @tz(Mars/Olympus_Mons)", "Mars/Olympus_Mons", 14),
+		("CRON_TZ=UTC 0 0 * * * @tz(UTC)", "@tz(UTC)", 22),
+		("0 0 * * * @bogus", "@bogus", 10),
+		("@weekly @catchup(all,0)", "0", 21),
+		("0 0 * * * @catchup(always)", "always", 19),
+		("@fortnightly", "@fortnightly", 0),
+		("@every 1h30 @startup", "1h30", 7),
+		("@every 1x30m", "1x30m", 7),
+		("@every 90s @anchor(tomorrow)", "tomorrow", 19),
+		("@every", "@every", 0),
+		("@at 2026-11-01T03:00 @tz(Mars/Olympus_Mons)", "Mars/Olympus_Mons", 25),
+		("@at 2026-13-01T03:00", "2026-13-01T03:00", 4)
+	];
+	for (interval, token, offset) in cases {
+		let e = interval.parse::<Schedule>().err().unwrap_or_else(|| panic!("{}", interval));
+		let found = e.token().unwrap_or_else(|| panic!("{}: {} has no token", interval, e));
+		assert_eq!((found.text.as_str(), found.offset), (token, offset), "{}: {}", interval, e);
+		assert_eq!(&interval[offset..offset + token.len()], token, "{}", interval);
+	}
+
+	// Errors about the interval as a whole don't point at a token
+	for interval in ["* * * *", "0 * * * * * *"] {
+		assert!(interval.parse::<Schedule>().unwrap_err().token().is_none(), "{}", interval);
+	}
+}
+
+#[tokio::test]
+async fn error_locations() {
+	let dir = tree("error_locations", &[("events.conf", "\
+Weekday:
+5 5 * * 5#6
+	SELECT 1;
+
+Bogus:
+0 0 * * * @bogus
+	SELECT 1;
+
+Tagged:
+0 0 * * * @blackout(nightly) @catchup(all,x)
+	SELECT 1;
+")]);
+	let path = dir.file("events.conf");
+	let e = crate::events::parse(&path, crate::events::Syntax::default(), crate::events::Validation::None, None).await.err().unwrap().to_string();
+
+	let expected = [
+		format!("error: Invalid cron syntax: 5#6 - occurrence must be between 1 and 5\n --> {}:2:9\n  |\n2 | 5 5 * * 5#6\n  |         ^^^", path),
+		format!("error: Invalid cron syntax: unexpected token @bogus\n --> {}:6:11\n  |\n6 | 0 0 * * * @bogus\n  |           ^^^^^^", path),
+		// Offsets account for blackout tags, which are removed before the interval is parsed
+		format!("error: Invalid cron value \"x\": invalid digit found in string\n  --> {}:10:43\n   |\n10 | 0 0 * * * @blackout(nightly) @catchup(all,x)\n   |                                           ^", path),
+		format!("error: could not parse {} due to 3 previous errors", path)
+	];
+	assert_eq!(e, expected.join("\n\n"));
+}
+
+#[test]
+fn display() {
+	let cases = ["*/15 10-20/5 */2 1/3 *", "1-5,10,20-30/5 0 * * *", "0 0 L * *", "0 0 15W * *", "0 0 1,L * 2#2", "0 0 * * 5L @startup", "CRON_TZ=Europe/Berlin @reboot",
+		"0 0 * * * @startup @catchup(once)", "0 0 * * * @catchup(all,10)", "*/10 * * * * *", "30 0 12 * * 1 @startup"];
+	for interval in cases {
+		assert_eq!(interval.parse::<CronInterval>().unwrap().to_string(), interval);
+	}
+}
+
+#[test]
+fn every() {
+	let anchor = Utc.with_ymd_and_hms(2024, 6, 3, 12, 0, 0).unwrap();
+	// (interval, seconds after the anchor, expected seconds after the anchor of the next run)
+	let cases: &[(&str, i64, i64)] = &[
+		("@every 90s", 0, 90),
+		("@every 90s", 89, 90),
+		("@every 90s", 90, 180),
+		("@every 2h30m", 3600, 9000),
+		("@every 1d", 86400 * 3 + 1, 86400 * 4),
+		("@every 7m", -1, 0), // Runs before the anchor follow the same period
+		("@every 7m", -420, 0),
+	];
+	for (interval, after, expected) in cases {
+		let parsed: Schedule = format!("{} @anchor({})", interval, anchor.to_rfc3339()).parse().unwrap();
+		let after = anchor + Duration::seconds(*after);
+		assert_eq!(parsed.next_after(&after), Some(anchor + Duration::seconds(*expected)), "{} after {}", interval, after);
+	}
+
+	// Fractional seconds round down, including before the anchor
+	let parsed: Schedule = format!("@every 90s @anchor({})", anchor.to_rfc3339()).parse().unwrap();
+	assert_eq!(parsed.next_after(&(anchor + Duration::milliseconds(89_500))), Some(anchor + Duration::seconds(90)));
+	assert_eq!(parsed.next_after(&(anchor + Duration::milliseconds(90_500))), Some(anchor + Duration::seconds(180)));
+	assert_eq!(parsed.next_after(&(anchor - Duration::milliseconds(500))), Some(anchor));
+	assert_eq!(parsed.next_after(&(anchor - Duration::nanoseconds(1))), Some(anchor));
+
+	// Intervals without an anchor are measured from when my_timers started
+	let parsed = EveryInterval::parse("@every 7m @startup", anchor).unwrap();
+	assert!(parsed.startup);
+	assert_eq!(parsed.next_after(&anchor), Some(anchor + Duration::minutes(7)));
+	assert_eq!(parsed.next_after(&(anchor + Duration::minutes(10))), Some(anchor + Duration::minutes(14)));
+
+	for interval in ["@every", "@every 0s", "@every 90", "@every m", "@every 1x", "@every 1h30", "@every 1m @reboot", "@every 1m @anchor(tomorrow)"] {
+		assert!(interval.parse::<Schedule>().is_err(), "{}", interval);
+	}
+	for interval in ["@every 1m30s", "@every 1d2h3m4s @startup @catchup(once)", "@every 7m @anchor(2024-06-03T12:00:00+00:00)", "0 0 * * * @startup"] {
+		assert_eq!(interval.parse::<Schedule>().unwrap().to_string(), interval);
+	}
+	assert_eq!("@every 90s".parse::<Schedule>().unwrap().to_string(), "@every 1m30s");
+}
+
+#[test]
+fn at() {
+	let parsed: Schedule = "@at 2026-11-01T03:00 @tz(Europe/Berlin)".parse().unwrap();
+	let time = Utc.with_ymd_and_hms(2026, 11, 1, 2, 0, 0).unwrap(); // CET, UTC+1
+	assert_eq!(parsed.next_after(&(time - Duration::days(30))), Some(time));
+	assert_eq!(parsed.next_after(&(time - Duration::seconds(1))), Some(time));
+	assert_eq!(parsed.next_after(&time), None);
+	assert_eq!(parsed.upcoming(&(time - Duration::days(1))).count(), 1);
+	assert!(!parsed.startup());
+	assert!(parsed.records_runs());
+
+	let parsed: Schedule = "@at 2026-11-01T03:00:00+01:00".parse().unwrap();
+	assert_eq!(parsed.next_after(&(time - Duration::seconds(1))), Some(time));
+
+	for interval in ["@at", "@at tomorrow", "@at 2026-11-01", "@at 2026-11-01T03:00 @startup", "@at 2026-03-29T02:30 @tz(Europe/Berlin)"] {
+		assert!(interval.parse::<Schedule>().is_err(), "{}", interval);
+	}
+	for interval in ["@at 2026-11-01T03:00:00 @tz(Europe/Berlin)", "@at 2026-11-01T03:00:00 @tz(UTC)"] {
+		assert_eq!(interval.parse::<Schedule>().unwrap().to_string(), interval);
+	}
+}
+
+#[test]
+fn hash() {
+	let resolve = |interval: &str, label: &str| -> String {
+		let mut parsed: CronInterval = interval.parse().unwrap();
+		parsed.resolve_hashes(label);
+		parsed.to_string()
+	};
+	// Hashes are stable, and differ between labels
+	assert_eq!(resolve("H * * * *", "Cleanup sessions"), resolve("H * * * *", "Cleanup sessions"));
+	let minutes: std::collections::HashSet<String> = (0..20).map(|i| resolve("H * * * *", &format!("Event {}", i))).collect();
+	assert!(minutes.len() > 10, "{:?}", minutes);
+
+	for i in 0..100 {
+		let label = format!("Event {}", i);
+		let fields: Vec<u32> = resolve("H H H H H", &label).split(' ').map(|v| v.parse().unwrap()).collect();
+		assert!(fields[0] <= 59 && fields[1] <= 23 && (1..=28).contains(&fields[2]) && (1..=12).contains(&fields[3]) && fields[4] <= 6, "{:?}", fields);
+
+		let minute: u32 = resolve("H(10-19) * * * *", &label).split(' ').next().unwrap().parse().unwrap();
+		assert!((10..=19).contains(&minute), "{}", minute);
+
+		let step = resolve("H/15 * * * *", &label);
+		let start: u32 = step.split('/').next().unwrap().parse().unwrap();
+		assert!(start < 15 && step.starts_with(&format!("{}/15 ", start)), "{}", step);
+
+		let step = resolve("H(30-59)/10 * * * *", &label);
+		let start: u32 = step.split('-').next().unwrap().parse().unwrap();
+		assert!((30..40).contains(&start) && step.starts_with(&format!("{}-59/10 ", start)), "{}", step);
+	}
+
+	// Unresolved hashes display as written and never match
+	let parsed: CronInterval = "H(0-29)/10 H * * *".parse().unwrap();
+	assert_eq!(parsed.to_string(), "H(0-29)/10 H * * *");
+	assert!(!parsed.match_time(&local((2024, 6, 3, 0, 0))));
+
+	for interval in ["H(30-10) * * * *", "H(0-60) * * * *", "H(0) * * * *", "H/0 * * * *"] {
+		assert!(interval.parse::<CronInterval>().is_err(), "{}", interval);
+	}
+}
+
+#[test]
+fn describe() {
+	let cases = [
+		("0 * * * 1-5", "at minute 0 past every hour on Monday through Friday"),
+		("* * * * *", "at every minute"),
+		("*/15 9-17 * * *", "at every 15th minute past every hour from 9 through 17"),
+		("30 9 * * MON,WED,FRI", "at 09:30 on Monday, Wednesday and Friday"),
+		("0 0 1,15 JAN-JUN *", "at 00:00 on day-of-month 1 and 15 in January through June"),
+		("0 0 1 * 1", "at 00:00 on day-of-month 1 or on Monday"),
+		("0 0 */2 * 1", "at 00:00 on every 2nd day-of-month and on Monday"),
+		("0 0 L * *", "at 00:00 on the last day of the month"),
+		("0 0 15W * *", "at 00:00 on the weekday nearest day-of-month 15"),
+		("0 12 * * 2#2,5L", "at 12:00 on the 2nd Tuesday of the month and the last Friday of the month"),
+		("0,30 * * * *", "at minute 0 and 30 past every hour"),
+		("*/10 * * * * *", "at every 10th second past every minute"),
+		("30 0 12 * * *", "at 12:00:30"),
+		("CRON_TZ=Europe/Berlin 0 0 * * * @startup @catchup(all,3)",
+			"at 00:00 (Europe/Berlin time), and when my_timers starts, making up to 3 missed runs"),
+		("@reboot", "when my_timers starts"),
+	];
+	for (interval, expected) in cases {
+		assert_eq!(interval.parse::<CronInterval>().unwrap().describe(), expected, "{}", interval);
+	}
+
+	let cases = [
+		("@every 7m", "every 7 minutes from when my_timers started"),
+		("@every 2h30m @catchup(once)", "every 2 hours and 30 minutes from when my_timers started, running once after missed runs"),
+		("@every 1d @anchor(2024-06-03T12:00:00Z)", "every day from "),
+		("@at 2026-11-01T03:00 @tz(Europe/Berlin)", "once at 2026-11-01T03:00:00+01:00"),
+	];
+	for (schedule, expected) in cases {
+		let description = schedule.parse::<Schedule>().unwrap().describe();
+		assert!(description.starts_with(expected), "{}: {}", schedule, description);
+	}
+}
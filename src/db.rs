@@ -1,6 +1,7 @@
 use serde::Deserialize;
 use self::error::DBConfigError;
 use sqlx::{mysql, postgres, sqlite, ConnectOptions};
+use sqlparser::dialect::{Dialect, MySqlDialect, PostgreSqlDialect, SQLiteDialect};
 use std::collections::HashSet;
 use lazy_static::lazy_static;
 
@@ -117,6 +118,16 @@ impl Config {
 		}
 	}
 
+	/// SQL dialect of the configured driver, for checking statements without connecting
+	pub fn dialect(&self) -> Result<Box<dyn Dialect>, DBConfigError> {
+		match self.driver.as_str() {
+			"mariadb" | "mysql" => Ok(Box::new(MySqlDialect {})),
+			"postgres" => Ok(Box::new(PostgreSqlDialect {})),
+			"sqlite" => Ok(Box::new(SQLiteDialect {})),
+			_ => Err(DBConfigError::InvalidDriver(self.driver.clone()))
+		}
+	}
+
 	// Print-friendly identifier containing protocol, address, database, and tls options
 	pub fn pretty_name(&self) -> String {
 		// Format here is inspired by mysql DSNs
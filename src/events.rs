@@ -1,109 +1,267 @@
-use std::{error::Error, fs::File, io::{BufReader, BufRead}, collections::{VecDeque, HashSet}, fmt::Display, pin::Pin};
+use std::{error::Error, collections::HashSet, fmt::Display, pin::Pin};
 
 use sqlx::{AnyPool, Executor};
+use sqlparser::{dialect::Dialect, parser::Parser};
 use tracing::{instrument, event, Level, span, Instrument};
-use tokio::sync::mpsc;
+use tokio::{sync::{mpsc, oneshot}, time};
 use chrono::Local;
 use lazy_static::lazy_static;
 
-use crate::cron::{self, error::CronParseError};
+use crate::cron;
+
+/// Log an event with event! if the event's log level allows it
+macro_rules! log {
+	($evt:expr, $level:expr, $($arg:tt)+) => {
+		if $evt.options.logs($level) {
+			event!($level, $($arg)+);
+		}
+	};
+}
 
 mod queue;
+pub mod error;
+mod parsing;
+mod statements;
+mod include;
+mod options;
 pub use queue::{EventTask, EventQueue};
+use error::{EventError, EventErrors, EventParseError};
+use parsing::{RawEvent, Source, Span};
+use include::Files;
+pub use options::Options;
+use statements::{Splitter, Statement};
+pub use statements::Syntax;
 
-#[derive(Debug)]
-pub enum EventParseError {
-	CronParseError(CronParseError),
-	SyntaxError(String),
-	SQLError(sqlx::Error)
-}
+#[cfg(test)]
+mod tests;
 
-impl Display for EventParseError {
-	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		match self {
-			Self::CronParseError(e) => e.fmt(f),
-			Self::SyntaxError(e) => write!(f, "Invalid event syntax: {}", e),
-			Self::SQLError(e) => e.fmt(f)
-		}
-	}
+/// How event SQL statements are validated while parsing
+pub enum Validation {
+	None,
+	Prepare(AnyPool), // Prepare each statement on the database
+	Parse(Box<dyn Dialect>) // Syntax-check each statement without connecting
 }
 
-impl Error for EventParseError {}
-
 /// A database event
 #[derive(Debug)]
 pub struct Event {
 	pub label: String,
-	pub interval: cron::CronInterval,
-	body: Vec<String> // Each stmt in an event body is validated as an SQL stmt during initial parsing
+	pub file: String, // Path of the events file the event is defined in
+	pub interval: cron::Schedule,
+	pub blackouts: Vec<String>, // Names of the blackouts the event is tagged with (@blackout(name))
+	pub options: Options,
+	pub warnings: Vec<EventError>, // Statements the embedded SQL parser couldn't read (check --sql), which may still be valid
+	body: Vec<Statement> // Each stmt in an event body is validated as an SQL stmt during initial parsing
 }
 
 impl Event {
-	async fn parse(evt_parts: &mut VecDeque<String>, pool: AnyPool) -> Result<Pin<Box<Event>>, EventParseError> {
-		if evt_parts.len() != 3 {
-			return Err(EventParseError::SyntaxError(format!("{} unexpected number of event tokens (expected {}, received {})",
-				evt_parts.get(1).unwrap_or(&"".into()), 3, evt_parts.len())));
-		}
-		// Parse label and interval
-		let mut evt = Event {
-			label: evt_parts.pop_front().unwrap().trim().to_string(),
-			interval: evt_parts.pop_front().unwrap().trim().parse()
-				.map_err(EventParseError::CronParseError)?,
-			body: Vec::new()
+	/// Parse and validate an event, returning every error found in it
+	async fn parse(raw: RawEvent, source: &Source, syntax: Syntax, validation: &Validation, known_blackouts: Option<&[&str]>) -> Result<Pin<Box<Event>>, Vec<EventError>> {
+		// Check the event's structure
+		let syntax_error = |e: String| vec![source.error(&raw.label, EventParseError::SyntaxError(e))];
+		let (label, options) = Options::split(&raw.label.text);
+		if label.is_empty() {
+			return Err(syntax_error("missing event name".into()));
+		}
+		if !raw.colon {
+			return Err(syntax_error(format!("{} - expected a colon after the event name", label)));
+		}
+		if raw.interval.is_empty() {
+			return Err(syntax_error(format!("{} - missing interval", label)));
+		}
+		if raw.body.is_empty() {
+			return Err(syntax_error(format!("{} - missing body (expected indented SQL statements)", label)));
+		}
+		let mut errors = Vec::new();
+
+		// Parse options
+		let options = match options {
+			Some((offset, options)) => Options::parse(options).map_err(|e| errors.extend(e.into_iter()
+				.map(|(start, len, e)| EventError {
+					location: source.location(&raw.label, offset + start, len),
+					error: EventParseError::OptionError(e)
+				}))).ok(),
+			None => Some(Options::default())
 		};
 
-		// Parse SQL body
-		let body = evt_parts.pop_front().unwrap();
-		let mut stmts: VecDeque<String> = body.split(";")
-			.map(|s| s.trim().replace("\t", "")) // Remove tabs
-			.filter(|s| s.len() > 0).collect();
+		// Parse interval, separating blackout tags from the interval.
+		// Tokens are kept with the line they're from and their offset in it, to point errors at them
+		let (blackouts, tokens): (Vec<_>, Vec<_>) = raw.interval.iter()
+			.flat_map(|span| cron::parsing::tokens(&span.text).into_iter().map(move |(offset, t)| (span, offset, t)))
+			.partition(|(_, _, t)| t.starts_with("@blackout(") && t.ends_with(')'));
+		let interval = tokens.iter().map(|(_, _, t)| *t).collect::<Vec<&str>>().join(" ");
+		let interval = interval.parse::<cron::Schedule>()
+			.map_err(|e| errors.push(Self::cron_error(&raw, &tokens, source, e)))
+			.ok();
+		let blackouts: Vec<String> = blackouts.iter().map(|(span, offset, t)| {
+			let name = &t["@blackout(".len()..t.len() - 1];
+			if known_blackouts.is_some_and(|known| !known.contains(&name)) {
+				errors.push(EventError {
+					location: source.location(span, offset + "@blackout(".len(), name.len()),
+					error: EventParseError::UnknownBlackout(name.to_string())
+				});
+			}
+			name.to_string()
+		}).collect();
+
+		// Parse SQL body. @file references are split along with the rest of the body, so an active DELIMITER applies to them,
+		// and their statements are validated against their own file
+		let mut body = Vec::new();
+		let mut splitter = Splitter::new(syntax);
+		for line in &raw.body {
+			let Some(path) = parsing::sql_file(line).filter(|_| !splitter.is_open()) else {
+				splitter.push(line);
+				continue;
+			};
+			Self::statements(splitter.finish(), source, validation, &mut body, &mut errors).await;
+			match Source::read(&source.relative(&path.text).to_string_lossy()) {
+				Ok(file) => {
+					for line in file.spans() {
+						splitter.push(&line);
+					}
+					Self::statements(splitter.finish(), &file, validation, &mut body, &mut errors).await;
+				},
+				Err(e) => errors.push(source.error(&path, EventParseError::FileError(path.text.clone(), e)))
+			}
+		}
+		Self::statements(splitter.finish(), source, validation, &mut body, &mut errors).await;
+
+		match (interval, options) {
+			(Some(mut interval), Some(options)) if errors.iter().all(|e| e.error.is_warning()) => {
+				interval.resolve_hashes(label);
+				Ok(Box::pin(Event {
+					label: label.to_string(),
+					file: source.path.clone(),
+					interval,
+					blackouts,
+					options,
+					warnings: errors,
+					body
+				}))
+			},
+			_ => Err(errors)
+		}
+	}
 
-		while let Some(stmt) = stmts.pop_front() {
-			// Validate SQL stmt
-			pool.prepare(&stmt).await
-				.map_err(EventParseError::SQLError)?;
+	/// Validate statements split from a source, adding them to the body
+	async fn statements(statements: Vec<(Span, Statement)>, source: &Source, validation: &Validation, body: &mut Vec<Statement>, errors: &mut Vec<EventError>) {
+		for (span, stmt) in statements {
+			// Validate SQL stmt. Compound statements can't be prepared, nor parsed by sqlparser, so they're checked when they run
+			let result = match validation {
+				_ if stmt.compound => Ok(()),
+				Validation::None => Ok(()),
+				Validation::Prepare(pool) => pool.prepare(&stmt.text).await
+					.map(|_| ())
+					.map_err(EventParseError::SQLError),
+				Validation::Parse(dialect) => Parser::parse_sql(dialect.as_ref(), &stmt.text)
+					.map(|_| ())
+					.map_err(EventParseError::SQLSyntaxError)
+			};
+			if let Err(e) = result {
+				errors.push(source.error(&span, e));
+			}
 			// Push to event body
-			evt.body.push(stmt);
+			body.push(stmt);
 		}
+	}
 
-		evt_parts.clear(); // Ensure the event parsing queue is empty
-		Ok(Box::pin(evt))
+	/// Point a cron error at the offending token, or at the whole interval if the error doesn't refer to one.
+	/// `tokens` are the interval's tokens as they were joined with spaces to be parsed: (line, offset in the line, token)
+	fn cron_error(raw: &RawEvent, tokens: &[(&Span, usize, &str)], source: &Source, e: cron::error::CronParseError) -> EventError {
+		let token = e.token().and_then(|token| {
+			// Find the interval token containing the error's token, by its offset in the joined interval
+			let mut start = 0;
+			tokens.iter().find_map(|(span, offset, t)| {
+				let found = (start..start + t.len()).contains(&token.offset)
+					.then(|| source.location(span, offset + token.offset - start, token.text.len()));
+				start += t.len() + 1;
+				found
+			})
+		});
+		let location = token.unwrap_or_else(|| {
+			let first = &raw.interval[0];
+			let last = raw.interval.iter().rfind(|s| s.line == first.line).unwrap_or(first);
+			source.location(first, 0, last.offset + last.text.len() - first.offset)
+		});
+		EventError {
+			error: EventParseError::CronParseError(e),
+			location
+		}
 	}
 
-	/// Run an event's SQL body on a transaction,
-	/// only committing the results if all statements succeed
+	/// Run an event's SQL body on a transaction, only committing the results if all statements succeed.
+	/// Failed runs are retried after a delay, which is waited for here instead of in the event queue,
+	/// so queued events aren't held up by another event's retries
 	#[instrument(skip_all, fields(event = %self, interval = %self.interval), err)]
 	pub async fn run<'e>(&'e self, pool: AnyPool, queue_tx: Option<mpsc::Sender<EventTask<'e>>>) -> Result<(), Box<dyn Error + 'e>> {
-		// Queue the event instead of immediately running if needed (non-concurrent drivers such as sqlite)
-		if let Some(tx) = queue_tx {
-			event!(Level::INFO, "Queueing event");
-			tx.send(EventTask{
-				event: self,
-				queued_at: Local::now()
-			}).await?;
-			return Ok(());
-		}
-
-		// Start a transaction to run the event on
-		event!(Level::INFO, "Running event");
-		let mut tx = pool.begin().await?;
+		let mut attempt = 0;
+		loop {
+			let result = match &queue_tx {
+				Some(tx) => self.queue(tx).await,
+				None => self.attempt(&pool).await
+			};
+			match result {
+				Err(e) if attempt < self.options.retries => {
+					attempt += 1;
+					event!(Level::WARN, "Run failed: {}, retrying in {:?} ({}/{})", e, retry_delay(attempt), attempt, self.options.retries);
+				},
+				result => return result.map_err(|e| e as Box<dyn Error>)
+			}
+			time::sleep(retry_delay(attempt)).await;
+		}
+	}
+
+	/// Queue the event instead of immediately running it (non-concurrent drivers such as sqlite),
+	/// waiting for the queued run to finish so callers know whether it succeeded
+	async fn queue<'e>(&'e self, tx: &mpsc::Sender<EventTask<'e>>) -> Result<(), Box<dyn Error + Send + Sync + 'e>> {
+		log!(self, Level::INFO, "Queueing event");
+		let (done_tx, done_rx) = oneshot::channel();
+		tx.send(EventTask{
+			event: self,
+			queued_at: Local::now(),
+			done: done_tx
+		}).await?;
+		match done_rx.await {
+			Ok(result) => result.map_err(Into::into),
+			Err(_) => Err("Event queue stopped before running the event".into())
+		}
+	}
+
+	/// Run the event body once
+	async fn attempt(&self, pool: &AnyPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+		log!(self, Level::INFO, "Running event");
+		log!(self, Level::TRACE, "Runs {}", self.interval.describe());
+		self.exec(pool).await?;
+		log!(self, Level::INFO, "Done");
+		Ok(())
+	}
+
+	/// Run the event body, failing runs that exceed the event's timeout
+	async fn exec(&self, pool: &AnyPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+		match self.options.timeout {
+			Some(timeout) => time::timeout(timeout, self.transaction(pool)).await
+				.unwrap_or_else(|_| Err(format!("Timed out after {:?}", timeout).into())),
+			None => self.transaction(pool).await
+		}
+	}
 
-		// Run the event body
-		let mut i: usize = 0;
-		for stmt in &self.body {
-			let span = span!(Level::DEBUG, "Exec", stmt = i,  action = Self::action(stmt));
+	/// Run the event body on a transaction, only committing the results if all statements succeed.
+	/// The transaction is rolled back if it's dropped before committing, i.e when timing out
+	async fn transaction(&self, pool: &AnyPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+		let mut tx = pool.begin().await?;
+		for (i, stmt) in self.body.iter().enumerate() {
+			let span = if self.options.logs(Level::DEBUG) {
+				span!(Level::DEBUG, "Exec", stmt = i,  action = Self::action(&stmt.text))
+			} else {
+				tracing::Span::none()
+			};
 			async {
-				let result = sqlx::query(stmt)
-					.execute(&mut *tx)
-					.await?;
-				event!(Level::DEBUG, "{} Rows affected", result.rows_affected());
+				let result = stmt.execute(&mut tx).await?;
+				log!(self, Level::DEBUG, "{} Rows affected", result.rows_affected());
 				Ok::<(), sqlx::Error>(())
 			}.instrument(span).await?;
-			i += 1;
 		}
-
 		tx.commit().await?;
-		event!(Level::INFO, "Done");
 		Ok(())
 	}
 
@@ -116,9 +274,10 @@ impl Event {
 			static ref END_UPDATE: HashSet<&'static str> = HashSet::from(["PARTITION", "FOR", "SET"]);
 			static ref END_DELETE: HashSet<&'static str> = HashSet::from(["PARTITION", "FOR", "WHERE", "ORDER", "LIMIT", "RETURNING", "BEFORE"]);
 		}
-		let tokens: Vec<&str> = stmt.trim().split(" ").collect();
-		let mut end = 2; // Exclusive end token index
-		match tokens[0].to_uppercase().as_str() {
+		let tokens: Vec<&str> = stmt.split_whitespace().collect();
+		// Exclusive end token index. Statements may be a single word, i.e VACUUM
+		let mut end = tokens.len().min(2);
+		match tokens.first().unwrap_or(&"").to_uppercase().as_str() {
 			"INSERT" => while end < tokens.len() && !(
 				END_INSERT.contains(tokens[end].to_ascii_uppercase().as_str()) ||
 				tokens[end].starts_with('('))
@@ -137,80 +296,56 @@ impl Event {
 	}
 }
 
+/// Delay before retrying a failed run, doubling with each attempt from 1s up to a minute
+fn retry_delay(attempt: u32) -> time::Duration {
+	time::Duration::from_secs(2u64.saturating_pow(attempt - 1).min(60))
+}
+
 impl Display for Event {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		write!(f, "{}", self.label)
 	}
 }
 
-#[instrument(name = "Parsing events", level = "debug", skip(pool), err)]
-pub async fn parse(path: &str, pool: AnyPool) -> Result<Vec<Pin<Box<Event>>>, Box<dyn Error>> {
+/// Parse the events file, or the *.conf files of a directory, and the files they include,
+/// validating each event's SQL statements. Events' @blackout tags are checked against `blackouts`, the configured blackouts,
+/// unless it's None (i.e when config.json isn't read)
+#[instrument(name = "Parsing events", level = "debug", skip(syntax, validation, blackouts), err)]
+pub async fn parse(path: &str, syntax: Syntax, validation: Validation, blackouts: Option<&[&str]>) -> Result<Vec<Pin<Box<Event>>>, Box<dyn Error>> {
 	event!(Level::DEBUG, "Parsing events");
-	// Open file reader
-	let file = File::open(path)?;
-	let reader = BufReader::new(file);
-
-	let mut events: Vec<Pin<Box<Event>>> = Vec::new();
-
-	// Iterate over lines
-	let mut evt_parts: VecDeque<String> = VecDeque::with_capacity(3);
-	for l in reader.lines().map(|l| -> String {
-		l.unwrap_or("".to_string())
-	}) {
-		// Discard comments
-		const COMMENT: &str = "#";
-		let l = l.splitn(2, COMMENT).nth(0).unwrap();
-
-		match evt_parts.len() {
-			0 => { // Label (maybe interval)
-				let mut l_parts: VecDeque<&str> = l.splitn(2, ':').collect();
-				if let Some(label) = l_parts.pop_front() {
-					evt_parts.push_back(label.into());
-				}
-				if let Some(interval) = l_parts.pop_front() {
-					evt_parts.push_back(interval.into());
-				}
-			},
-			1 => { // Continue multiline label (maybe interval)
-				let mut l_parts: VecDeque<&str> = l.splitn(2, ':').collect();
-				if let Some(label) = l_parts.pop_front() {
-					evt_parts[0].push(' ');
-					evt_parts[0].push_str(label);
-				}
-				if let Some(interval) = l_parts.pop_front() {
-					evt_parts.push_back(interval.into())
-				}
-			},
-			2 => { // Continue/end multiline interval
-				// An indented line marks the beginning of an event body
-				if l.starts_with('\t') || l.starts_with("  ") {
-					evt_parts.push_back(l.into());
-				} else {
-					evt_parts[1].push_str(&l);
-				}
-			},
-			3 => { // Body
-				if l.starts_with('\t') || l.starts_with("  ") {
-					evt_parts[2].push(' ');
-					evt_parts[2].push_str(&l);
-				} else {
-					// Parse event
-					events.push(Event::parse(&mut evt_parts, pool.clone()).await?);
-				}
-			},
-			_ => panic!("event parsing dequeue exceeded max size of 3")
-		};
+	let files = Files::load(path, syntax)?;
+
+	// Every event is parsed, so all errors in the files can be reported at once
+	let mut results = Vec::new();
+	for entry in files.events {
+		results.push(match entry {
+			Ok((i, raw)) => Event::parse(raw, &files.sources[i], syntax, &validation, blackouts).await,
+			Err(e) => Err(vec![e])
+		});
 	}
-	// If there is no terminating newline, the last event still needs to be pushed
-	if evt_parts.len() == 3 {
-		events.push(Event::parse(&mut evt_parts, pool.clone()).await?);
+	if results.iter().any(Result::is_err) {
+		// Warnings of valid events are reported along with the errors, in the order they're found
+		let errors = results.into_iter()
+			.flat_map(|result| match result {
+				Ok(evt) => Pin::into_inner(evt).warnings,
+				Err(errors) => errors
+			})
+			.collect();
+		return Err(Box::new(EventErrors {
+			path: path.to_string(),
+			errors
+		}));
 	}
+	let events: Vec<Pin<Box<Event>>> = results.into_iter().map(Result::unwrap).collect();
 
 	{
 		// Displayable events
 		let mut d_events: Vec<String> = Vec::with_capacity(events.len());
+		let now = Local::now();
 		for evt in &events {
-			d_events.push(format!("{}", evt));
+			let upcoming: Vec<String> = evt.interval.upcoming(&now).take(3)
+				.map(|t| t.with_timezone(&Local).to_rfc3339()).collect();
+			d_events.push(format!("{} ({}: {}) from {} next runs: [{}]", evt, evt.interval, evt.interval.describe(), evt.file, upcoming.join(", ")));
 		}
 		event!(Level::TRACE, "Loaded events:\n\t{}", d_events.join("\n\t"));
 	}
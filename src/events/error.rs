@@ -0,0 +1,129 @@
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::cron::error::CronParseError;
+
+#[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum EventParseError {
+	CronParseError(CronParseError),
+	SyntaxError(String),
+	SQLError(sqlx::Error),
+	SQLSyntaxError(sqlparser::parser::ParserError),
+	IncludeError(String),
+	FileError(String, std::io::Error),
+	OptionError(String),
+	UnknownBlackout(String)
+}
+
+impl Display for EventParseError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::CronParseError(e) => e.fmt(f),
+			Self::SyntaxError(e) => write!(f, "Invalid event syntax: {}", e),
+			Self::SQLError(e) => e.fmt(f),
+			Self::SQLSyntaxError(e) => write!(f, "Invalid SQL: {}", e),
+			Self::IncludeError(e) => write!(f, "Invalid include: {}", e),
+			Self::FileError(path, e) => write!(f, "Can't read {}: {}", path, e),
+			Self::OptionError(e) => write!(f, "Invalid event option: {}", e),
+			Self::UnknownBlackout(name) => write!(f, "Unknown blackout {} (blackouts are configured in config.json)", name)
+		}
+	}
+}
+
+impl Error for EventParseError {}
+
+impl EventParseError {
+	/// Whether the error is only a warning, which doesn't make the event invalid.
+	/// The embedded SQL parser doesn't cover every dialect feature, so statements it can't read may still be valid
+	pub fn is_warning(&self) -> bool {
+		matches!(self, Self::SQLSyntaxError(_))
+	}
+}
+
+/// Position of a snippet in the events file
+#[derive(Debug, Clone)]
+pub struct Location {
+	pub path: String,
+	pub line: usize, // 1-based
+	pub column: usize, // 1-based, in characters
+	pub len: usize, // Length of the snippet in characters
+	pub source: String // Full text of the line
+}
+
+impl Location {
+	/// A whole file, for errors that don't refer to a line of it
+	pub fn file(path: &str) -> Self {
+		Self {
+			path: path.to_string(),
+			line: 0,
+			column: 0,
+			len: 0,
+			source: String::new()
+		}
+	}
+}
+
+/// An error parsing an event, with the location of the offending snippet
+#[derive(Debug)]
+pub struct EventError {
+	pub error: EventParseError,
+	pub location: Location
+}
+
+/// Compiler-like error format, i.e:
+/// ```text
+/// error: Invalid cron syntax: value 60 exceeds field range 0-59
+///   --> events.conf:12:1
+///    |
+/// 12 | 60 * * * *
+///    | ^^
+/// ```
+impl Display for EventError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let l = &self.location;
+		let severity = if self.error.is_warning() { "warning" } else { "error" };
+		if l.line == 0 {
+			writeln!(f, "{}: {}", severity, self.error)?;
+			return write!(f, " --> {}", l.path);
+		}
+		let gutter = " ".repeat(l.line.to_string().len());
+		// Tabs are expanded so the underline lines up with the snippet
+		let source = l.source.replace('\t', "    ");
+		let column: usize = l.source.chars().take(l.column - 1).map(|c| if c == '\t' { 4 } else { 1 }).sum();
+		writeln!(f, "{}: {}", severity, self.error)?;
+		writeln!(f, "{}--> {}:{}:{}", gutter, l.path, l.line, l.column)?;
+		writeln!(f, "{} |", gutter)?;
+		writeln!(f, "{} | {}", l.line, source.trim_end())?;
+		write!(f, "{} | {}{}", gutter, " ".repeat(column), "^".repeat(l.len.max(1)))
+	}
+}
+
+impl Error for EventError {}
+
+/// Every error in an events file, including warnings
+#[derive(Debug)]
+pub struct EventErrors {
+	pub path: String,
+	pub errors: Vec<EventError>
+}
+
+impl Display for EventErrors {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		for e in &self.errors {
+			writeln!(f, "{}\n", e)?;
+		}
+		let warnings = self.errors.iter().filter(|e| e.error.is_warning()).count();
+		match self.errors.len() - warnings {
+			1 => write!(f, "error: could not parse {} due to 1 previous error", self.path)?,
+			n => write!(f, "error: could not parse {} due to {} previous errors", self.path, n)?
+		}
+		match warnings {
+			0 => Ok(()),
+			1 => write!(f, "; 1 warning emitted"),
+			n => write!(f, "; {} warnings emitted", n)
+		}
+	}
+}
+
+impl Error for EventErrors {}
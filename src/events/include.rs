@@ -0,0 +1,150 @@
+use std::{collections::HashSet, fs, io, path::{Path, PathBuf}};
+
+use super::error::{EventError, EventParseError, Location};
+use super::parsing::{Entry, RawEvent, Source, Span};
+use super::statements::Syntax;
+
+#[cfg(test)]
+mod tests;
+
+/// Events files, read by following include lines from an events file or directory
+pub(super) struct Files {
+	pub sources: Vec<Source>,
+	// Each event with the index of its source, or an include error, in the order they're read
+	pub events: Vec<Result<(usize, RawEvent), EventError>>,
+	syntax: Syntax, // Where literals and comments end, which decides where events end
+	stack: Vec<PathBuf>, // Files being read, to detect include cycles
+	loaded: HashSet<PathBuf> // Files already read, which are only read once
+}
+
+impl Files {
+	/// Read an events file, or the *.conf files in a directory in sorted order.
+	/// Only failing to read `path` itself is an error, files that can't be read are recorded with the events
+	pub fn load(path: &str, syntax: Syntax) -> io::Result<Self> {
+		let mut files = Self {
+			sources: Vec::new(),
+			events: Vec::new(),
+			syntax,
+			stack: Vec::new(),
+			loaded: HashSet::new()
+		};
+		let path = Path::new(path);
+		if !path.is_dir() {
+			files.read(path)?;
+			return Ok(files);
+		}
+		for path in conf_files(path)? {
+			if let Err(e) = files.read(&path) {
+				let path = path.display().to_string();
+				files.events.push(Err(EventError {
+					location: Location::file(&path),
+					error: EventParseError::FileError(path, e)
+				}));
+			}
+		}
+		Ok(files)
+	}
+
+	/// Read a file, following its include lines
+	fn read(&mut self, path: &Path) -> io::Result<()> {
+		let canonical = path.canonicalize()?;
+		if self.loaded.contains(&canonical) {
+			return Ok(());
+		}
+		let source = Source::read(&path.to_string_lossy())?;
+		self.loaded.insert(canonical.clone());
+		let i = self.sources.len();
+		let entries = source.entries(self.syntax);
+		self.sources.push(source);
+
+		self.stack.push(canonical);
+		for entry in entries {
+			match entry {
+				Entry::Event(raw) => self.events.push(Ok((i, raw))),
+				Entry::Include(span) => self.include(i, &span)
+			}
+		}
+		self.stack.pop();
+		Ok(())
+	}
+
+	/// Read the files matched by an include line, relative to the including file
+	fn include(&mut self, i: usize, span: &Span) {
+		let pattern = self.sources[i].relative(&span.text);
+
+		let mut paths = Vec::new();
+		match glob::glob(&pattern.to_string_lossy()) {
+			Ok(matches) => for path in matches {
+				match path {
+					Ok(path) => paths.push(path),
+					// Directories that can't be listed while matching the pattern
+					Err(e) => {
+						let path = e.path().to_path_buf();
+						self.file_error(i, span, &path, e.into());
+					}
+				}
+			},
+			Err(e) => return self.error(i, span, EventParseError::IncludeError(format!("{} - {}", span.text, e)))
+		}
+		// Patterns may match nothing, but plain paths must exist
+		if paths.is_empty() && !span.text.contains(['*', '?', '[']) {
+			let e = format!("{} - no such file or directory", span.text);
+			return self.error(i, span, EventParseError::IncludeError(e));
+		}
+
+		for path in paths {
+			if !path.is_dir() {
+				self.include_file(i, span, &path);
+				continue;
+			}
+			match conf_files(&path) {
+				Ok(files) => for file in files {
+					self.include_file(i, span, &file);
+				},
+				Err(e) => self.file_error(i, span, &path, e)
+			}
+		}
+	}
+
+	/// Read a file included by an include line
+	fn include_file(&mut self, i: usize, span: &Span, path: &Path) {
+		let canonical = match path.canonicalize() {
+			Ok(canonical) => canonical,
+			Err(e) => return self.file_error(i, span, path, e)
+		};
+		// Including a file that is being read would never end
+		if let Some(start) = self.stack.iter().position(|p| *p == canonical) {
+			let cycle: Vec<String> = self.stack[start..].iter().chain([&canonical])
+				.map(|p| p.display().to_string()).collect();
+			let e = format!("include cycle {}", cycle.join(" -> "));
+			return self.error(i, span, EventParseError::IncludeError(e));
+		}
+		if let Err(e) = self.read(path) {
+			self.file_error(i, span, path, e);
+		}
+	}
+
+	/// Record an error in an include line
+	fn error(&mut self, i: usize, span: &Span, e: EventParseError) {
+		let error = self.sources[i].error(span, e);
+		self.events.push(Err(error));
+	}
+
+	/// Record a file that an include line refers to but can't be read
+	fn file_error(&mut self, i: usize, span: &Span, path: &Path, e: io::Error) {
+		self.error(i, span, EventParseError::FileError(path.display().to_string(), e));
+	}
+}
+
+/// The *.conf files in a directory, in sorted order
+fn conf_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+	let mut paths = Vec::new();
+	for entry in fs::read_dir(dir)? {
+		let path = entry?.path();
+		if path.is_file() && path.extension().is_some_and(|e| e == "conf") {
+			paths.push(path);
+		}
+	}
+	paths.sort();
+	Ok(paths)
+}
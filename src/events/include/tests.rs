@@ -0,0 +1,98 @@
+use std::{fs, path::Path};
+
+use super::Files;
+use crate::events::Syntax;
+use crate::test_util::tree;
+
+/// An event, followed by include lines
+fn event(label: &str, includes: &[&str]) -> String {
+	let includes: String = includes.iter().map(|i| format!("\ninclude {}", i)).collect();
+	format!("{}:\n* * * * *\n\tSELECT 1;\n{}\n", label, includes)
+}
+
+/// Labels of the loaded events in order, and the first line of each error
+fn load(path: &Path) -> (Vec<String>, Vec<String>) {
+	let files = Files::load(&path.to_string_lossy(), Syntax::default()).unwrap();
+	let mut labels = Vec::new();
+	let mut errors = Vec::new();
+	for entry in files.events {
+		match entry {
+			Ok((_, raw)) => labels.push(raw.label.text),
+			Err(e) => errors.push(e.to_string().lines().next().unwrap().to_string())
+		}
+	}
+	(labels, errors)
+}
+
+#[test]
+fn cycles() {
+	let dir = tree("include_cycles", &[
+		("a.conf", &event("A", &["b.conf"])),
+		("b.conf", &event("B", &["a.conf", "b.conf"]))
+	]);
+	let (labels, errors) = load(&dir.join("a.conf"));
+
+	let (a, b) = (dir.join("a.conf").display().to_string(), dir.join("b.conf").display().to_string());
+	assert_eq!(labels, ["A", "B"]);
+	assert_eq!(errors, [
+		format!("error: Invalid include: include cycle {} -> {} -> {}", a, b, a),
+		format!("error: Invalid include: include cycle {} -> {}", b, b)
+	]);
+}
+
+#[test]
+fn diamonds() {
+	// Files included more than once are only read the first time
+	let dir = tree("include_diamonds", &[
+		("main.conf", &event("Main", &["left.conf", "right.conf"])),
+		("left.conf", &event("Left", &["shared.conf"])),
+		("right.conf", &event("Right", &["./shared.conf"])),
+		("shared.conf", &event("Shared", &[]))
+	]);
+	let (labels, errors) = load(&dir.join("main.conf"));
+
+	assert_eq!(labels, ["Main", "Left", "Shared", "Right"]);
+	assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn ordering() {
+	let dir = tree("include_ordering", &[
+		("events.d/b.conf", &event("B", &["../teams/*.conf", "../nested"])),
+		("events.d/10.conf", &event("10", &[])),
+		("events.d/2.conf", &event("2", &[])),
+		("events.d/notes.txt", "Not events"),
+		("teams/z.conf", &event("Z", &[])),
+		("teams/y.conf", &event("Y", &[])),
+		("nested/2.conf", &event("Nested 2", &[])),
+		("nested/1.conf", &event("Nested 1", &[])),
+		("nested/deeper/1.conf", &event("Deeper", &[]))
+	]);
+	let (labels, errors) = load(&dir.join("events.d"));
+
+	// Directories and glob matches are read in sorted order, directories only include their own *.conf files
+	assert_eq!(labels, ["10", "2", "B", "Y", "Z", "Nested 1", "Nested 2"]);
+	assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn file_errors() {
+	let dir = tree("include_file_errors", &[
+		("events.d/a.conf", &event("A", &["../missing.conf", "../invalid.txt"])),
+		("events.d/b.conf", &event("B", &[])),
+		("invalid.txt", "")
+	]);
+	// Files that aren't UTF-8 can't be read
+	fs::write(dir.join("invalid.txt"), [0xff, 0xfe, 0xfd]).unwrap();
+	fs::write(dir.join("events.d/c.conf"), [0xff, 0xfe, 0xfd]).unwrap();
+	let (labels, errors) = load(&dir.join("events.d"));
+	assert!(Files::load(&dir.join("missing.conf").to_string_lossy(), Syntax::default()).is_err());
+
+	// Files that can't be read are reported with the other errors, instead of stopping at the first
+	assert_eq!(labels, ["A", "B"]);
+	assert_eq!(errors, [
+		"error: Invalid include: ../missing.conf - no such file or directory".to_string(),
+		format!("error: Can't read {}: stream did not contain valid UTF-8", dir.join("events.d/../invalid.txt").display()),
+		format!("error: Can't read {}: stream did not contain valid UTF-8", dir.join("events.d/c.conf").display())
+	]);
+}
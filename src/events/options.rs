@@ -0,0 +1,111 @@
+use std::{fmt::Display, time::Duration};
+
+use tracing::Level;
+
+use crate::cron;
+
+#[cfg(test)]
+mod tests;
+
+/// Keys accepted in an event's options
+const KEYS: &[&str] = &["enabled", "timeout", "retries", "tags", "log_level"];
+
+/// Per-event options, set in brackets after the event's name (i.e `name [timeout=30s retries=3]:`)
+#[derive(Debug)]
+pub struct Options {
+	pub enabled: bool, // Disabled events are validated, but never run
+	pub timeout: Option<Duration>, // Time after which a run is rolled back and fails
+	pub retries: u32, // Number of times a failed run is retried
+	pub tags: Vec<String>,
+	pub log_level: Level // Most verbose level logged for the event's runs
+}
+
+impl Default for Options {
+	fn default() -> Self {
+		Self {
+			enabled: true,
+			timeout: None,
+			retries: 0,
+			tags: Vec::new(),
+			log_level: Level::TRACE
+		}
+	}
+}
+
+impl Options {
+	/// Split a label into the event's name and its options, with their byte offset in the label.
+	/// Brackets at the end of the label are always options, so invalid options are reported instead of ending up in the name
+	pub fn split(label: &str) -> (&str, Option<(usize, &str)>) {
+		match (label.rfind('['), label.strip_suffix(']')) {
+			(Some(start), Some(_)) => (label[..start].trim_end(), Some((start + 1, &label[start + 1..label.len() - 1]))),
+			_ => (label, None)
+		}
+	}
+
+	/// Parse whitespace-separated `key=value` options,
+	/// returning the byte offset, length and message of every invalid option
+	pub fn parse(s: &str) -> Result<Self, Vec<(usize, usize, String)>> {
+		let mut options = Self::default();
+		let mut errors = Vec::new();
+		for (start, option) in cron::parsing::tokens(s) {
+			let Some((key, value)) = option.split_once('=') else {
+				errors.push((start, option.len(), format!("{} - expected key=value", option)));
+				continue;
+			};
+			let result = match key {
+				"enabled" => value.parse().map(|enabled| options.enabled = enabled)
+					.map_err(|_| format!("{} - expected true or false", option)),
+				"timeout" => cron::parse_duration(value)
+					.ok().and_then(|d| d.to_std().ok())
+					.map(|timeout| options.timeout = Some(timeout))
+					.ok_or_else(|| format!("{} - invalid duration (expected i.e 30s, 5m, 1h)", option)),
+				"retries" => value.parse().map(|retries| options.retries = retries)
+					.map_err(|e| format!("{} - {}", option, e)),
+				"tags" if value.split(',').all(|t| !t.is_empty()) => {
+					options.tags = value.split(',').map(String::from).collect();
+					Ok(())
+				},
+				"tags" => Err(format!("{} - expected comma-separated tags", option)),
+				"log_level" => value.parse().map(|level| options.log_level = level)
+					.map_err(|_| format!("{} - expected trace, debug, info, warn or error", option)),
+				_ => {
+					errors.push((start, key.len(), format!("unknown option {} (expected {})", key, KEYS.join(", "))));
+					continue;
+				}
+			};
+			if let Err(e) = result {
+				errors.push((start, option.len(), e));
+			}
+		}
+		if errors.is_empty() { Ok(options) } else { Err(errors) }
+	}
+
+	/// Whether the event's runs log at a level
+	pub fn logs(&self, level: Level) -> bool {
+		level <= self.log_level
+	}
+}
+
+impl Display for Options {
+	/// Options that aren't set to their default, as they're written in events.conf
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let default = Self::default();
+		let mut options = Vec::new();
+		if self.enabled != default.enabled {
+			options.push(format!("enabled={}", self.enabled));
+		}
+		if let Some(timeout) = self.timeout {
+			options.push(format!("timeout={}s", timeout.as_secs()));
+		}
+		if self.retries != default.retries {
+			options.push(format!("retries={}", self.retries));
+		}
+		if !self.tags.is_empty() {
+			options.push(format!("tags={}", self.tags.join(",")));
+		}
+		if self.log_level != default.log_level {
+			options.push(format!("log_level={}", self.log_level.as_str().to_lowercase()));
+		}
+		write!(f, "{}", options.join(" "))
+	}
+}
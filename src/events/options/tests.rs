@@ -0,0 +1,51 @@
+use std::time::Duration;
+use tracing::Level;
+
+use super::Options;
+
+#[test]
+fn parse() {
+	let options = Options::parse("enabled=false  timeout=1m30s retries=3 tags=auth,cleanup log_level=warn").unwrap();
+	assert!(!options.enabled);
+	assert_eq!(options.timeout, Some(Duration::from_secs(90)));
+	assert_eq!(options.retries, 3);
+	assert_eq!(options.tags, ["auth", "cleanup"]);
+	assert_eq!(options.log_level, Level::WARN);
+	assert_eq!(options.to_string(), "enabled=false timeout=90s retries=3 tags=auth,cleanup log_level=warn");
+	assert_eq!(Options::parse("").unwrap().to_string(), "");
+
+	// Every invalid option is reported, with its offset and length
+	let errors = Options::parse("enabled=no timeout=30x retries=-1 tags=a,,b log_level=loud").err().unwrap();
+	let spans: Vec<(usize, usize)> = errors.iter().map(|(start, len, _)| (*start, *len)).collect();
+	assert_eq!(spans, [(0, 10), (11, 11), (23, 10), (34, 9), (44, 14)]);
+	assert!(errors[0].2.contains("expected true or false"));
+	assert!(errors[1].2.contains("invalid duration"));
+
+	// Options that aren't key=value are rejected, instead of being read as part of the name
+	let errors = Options::parse("enabled=false nightly").err().unwrap();
+	assert_eq!(errors, [(14, 7, "nightly - expected key=value".to_string())]);
+}
+
+#[test]
+fn unknown_keys() {
+	let errors = Options::parse("timeout=30s retry=3 priority=1").err().unwrap();
+	let errors: Vec<(usize, usize, &str)> = errors.iter().map(|(start, len, e)| (*start, *len, e.as_str())).collect();
+	assert_eq!(errors, [
+		(12, 5, "unknown option retry (expected enabled, timeout, retries, tags, log_level)"),
+		(20, 8, "unknown option priority (expected enabled, timeout, retries, tags, log_level)")
+	]);
+}
+
+#[test]
+fn split() {
+	assert_eq!(Options::split("Expire sessions [timeout=30s retries=3]"), ("Expire sessions", Some((17, "timeout=30s retries=3"))));
+	assert_eq!(Options::split("Expire sessions[ enabled=false ]"), ("Expire sessions", Some((16, " enabled=false "))));
+	assert_eq!(Options::split("Expire sessions"), ("Expire sessions", None));
+
+	// Brackets elsewhere in the name are part of it, trailing brackets are always options
+	assert_eq!(Options::split("Backup [nightly] at 2am"), ("Backup [nightly] at 2am", None));
+	assert_eq!(Options::split("Backup [nightly]"), ("Backup", Some((8, "nightly"))));
+	assert_eq!(Options::split("Backup []"), ("Backup", Some((8, ""))));
+	// Only the last brackets can be options
+	assert_eq!(Options::split("Backup [nightly] [retries=1]"), ("Backup [nightly]", Some((18, "retries=1"))));
+}
@@ -0,0 +1,204 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::error::{EventError, EventParseError, Location};
+use super::statements::{Splitter, Syntax};
+
+/// Text from a line of the events file, with its position
+#[derive(Debug, Clone)]
+pub struct Span {
+	pub line: usize, // 1-based
+	pub offset: usize, // Byte offset of the text in the line
+	pub text: String
+}
+
+/// The parts of an event, before they're parsed
+#[derive(Debug)]
+pub struct RawEvent {
+	pub label: Span, // Joined label lines, positioned at the first
+	pub colon: bool, // Whether the label ended with a colon
+	pub interval: Vec<Span>, // Each line of the interval
+	pub body: Vec<Span> // Each line of the body, including comments
+}
+
+/// An item of an events file
+#[derive(Debug)]
+pub enum Entry {
+	Event(RawEvent),
+	Include(Span) // Path or glob pattern of an include line
+}
+
+/// An events file, kept to point errors at their source
+pub struct Source {
+	pub path: String,
+	lines: Vec<String>
+}
+
+impl Source {
+	pub fn read(path: &str) -> io::Result<Self> {
+		Ok(Self {
+			path: path.to_string(),
+			lines: fs::read_to_string(path)?.lines().map(String::from).collect()
+		})
+	}
+
+	/// Each line of the file, i.e for the statements of an SQL file
+	pub fn spans(&self) -> Vec<Span> {
+		self.lines.iter().enumerate()
+			.map(|(i, text)| Span { line: i + 1, offset: 0, text: text.clone() })
+			.collect()
+	}
+
+	/// Path relative to the file's directory
+	pub fn relative(&self, path: &str) -> PathBuf {
+		Path::new(&self.path).parent().unwrap_or(Path::new("")).join(path)
+	}
+
+	/// Location of a span's text, or of a snippet starting `start` bytes into it
+	pub fn location(&self, span: &Span, start: usize, len: usize) -> Location {
+		let source = self.lines.get(span.line - 1).cloned().unwrap_or_default();
+		let offset = (span.offset + start).min(source.len());
+		let end = (offset + len).min(source.len());
+		Location {
+			path: self.path.clone(),
+			line: span.line,
+			column: source[..offset].chars().count() + 1,
+			len: source[offset..end].chars().count(),
+			source
+		}
+	}
+
+	/// Error pointing at a span's text
+	pub fn error(&self, span: &Span, error: EventParseError) -> EventError {
+		EventError {
+			location: self.location(span, 0, span.text.len()),
+			error
+		}
+	}
+
+	/// Split the file into events and include lines.
+	/// Each event starts with a label ending in a colon, optionally followed by its interval on the same line.
+	/// The interval may continue across lines until the body, which is made of indented lines.
+	/// Incomplete events are returned as-is, and reported by Event::parse.
+	pub fn entries(&self, syntax: Syntax) -> Vec<Entry> {
+		let mut entries = Vec::new();
+		let mut current: Option<RawEvent> = None;
+		let mut splitter = Splitter::new(syntax); // Reads the current event's body, to find multi-line literals
+		for (i, raw) in self.lines.iter().enumerate() {
+			let l = strip_comment(raw);
+			let line = i + 1;
+			// Lines inside a multi-line literal or block comment are part of the body, whatever they contain
+			if let Some(evt) = current.as_mut().filter(|_| splitter.is_open()) {
+				let span = Span { line, offset: 0, text: raw.clone() };
+				splitter.push(&span);
+				evt.body.push(span);
+				continue;
+			}
+			// Lines that only contain a comment are skipped, while blank lines end the body
+			if l.trim().is_empty() && !raw.trim().is_empty() {
+				continue;
+			}
+			let indented = l.starts_with('\t') || l.starts_with("  ");
+
+			// An indented line after the interval is part of the body, kept as-is for the statement splitter
+			if let Some(evt) = current.as_mut().filter(|evt| evt.colon && indented) {
+				let span = Span { line, offset: 0, text: raw.clone() };
+				splitter.push(&span);
+				evt.body.push(span);
+				continue;
+			}
+			if l.trim().is_empty() {
+				// Blank lines end the body
+				if current.as_ref().is_some_and(|evt| !evt.body.is_empty()) {
+					entries.push(Entry::Event(current.take().unwrap()));
+				}
+				continue;
+			}
+			// A non-indented line after the body starts the next event
+			if current.as_ref().is_some_and(|evt| !evt.body.is_empty()) {
+				entries.push(Entry::Event(current.take().unwrap()));
+			}
+			// Include lines are only read between events
+			if let Some(path) = include(l).filter(|_| current.is_none()) {
+				entries.push(Entry::Include(trimmed(line, l.len() - path.len(), path)));
+				continue;
+			}
+
+			match current.as_mut() {
+				Some(evt) if evt.colon => { // Continue multiline interval
+					evt.interval.push(trimmed(line, 0, l));
+				},
+				_ => { // Label (maybe interval)
+					let (label, interval) = match l.split_once(':') {
+						Some((label, interval)) => (label, Some(interval)),
+						None => (l, None)
+					};
+					match current.as_mut() {
+						Some(evt) => { // Continue multiline label
+							evt.label.text.push(' ');
+							evt.label.text.push_str(label.trim());
+						},
+						None => {
+							current = Some(RawEvent {
+								label: trimmed(line, 0, label),
+								colon: false,
+								interval: Vec::new(),
+								body: Vec::new()
+							});
+							splitter = Splitter::new(syntax);
+						}
+					}
+					let evt = current.as_mut().unwrap();
+					evt.colon = interval.is_some();
+					if let Some(interval) = interval.filter(|i| !i.trim().is_empty()) {
+						evt.interval.push(trimmed(line, label.len() + 1, interval));
+					}
+				}
+			}
+		}
+		// If there is no terminating newline, the last event still needs to be pushed
+		entries.extend(current.map(Entry::Event));
+		entries
+	}
+}
+
+/// Path of an `include path/or/glob*.conf` line, which can't contain a colon so it isn't mistaken for an event label
+fn include(l: &str) -> Option<&str> {
+	let path = l.strip_prefix("include")?;
+	(path.starts_with(char::is_whitespace) && !path.trim().is_empty() && !path.contains(':')).then_some(path)
+}
+
+/// Path of an `@file path.sql` body line, whose statements are read from the file
+pub fn sql_file(line: &Span) -> Option<Span> {
+	let l = strip_comment(&line.text);
+	let start = l.len() - l.trim_start().len();
+	let path = l.trim_start().strip_prefix("@file")?;
+	path.starts_with(char::is_whitespace)
+		.then(|| trimmed(line.line, line.offset + start + "@file".len(), path))
+		.filter(|span| !span.text.is_empty())
+}
+
+/// Span of text with surrounding whitespace removed
+fn trimmed(line: usize, offset: usize, text: &str) -> Span {
+	let start = text.len() - text.trim_start().len();
+	Span {
+		line,
+		offset: offset + start,
+		text: text.trim().to_string()
+	}
+}
+
+/// Remove a comment from a line. Comments start with # at the start of a line or after whitespace,
+/// so # can still be used in cron intervals (i.e 2#2).
+fn strip_comment(l: &str) -> &str {
+	const COMMENT: char = '#';
+	let mut prev = ' ';
+	for (i, c) in l.char_indices() {
+		if c == COMMENT && prev.is_whitespace() {
+			return &l[..i];
+		}
+		prev = c;
+	}
+	l
+}
@@ -1,15 +1,16 @@
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 use chrono::{DateTime, Local};
 use std::{cmp, error::Error};
 use sqlx::AnyPool;
-use tracing::{instrument, event, Level, span, Instrument};
+use tracing::{instrument, event, Level};
 use super::Event;
 
 /// A deferred event added to the global event queue.
 /// Used with sqlite to prevent write lock contention
 pub struct EventTask<'e> {
 	pub event: &'e Event,
-	pub queued_at: DateTime<Local>
+	pub queued_at: DateTime<Local>,
+	pub done: oneshot::Sender<Result<(), String>> // The run's result, sent once it's done
 }
 
 pub struct EventQueue<'e> {
@@ -34,31 +35,16 @@ impl EventQueue<'_> {
 }
 
 impl EventTask<'_> {
-	/// Equivalent to Event::run for an EventTask pulled from a queue.
-	/// Used with non-concurrent drivers.
-	#[instrument(skip_all, fields(event = %self.event, interval = %self.event.interval), err)]
+	/// Equivalent to Event::run for an EventTask pulled from a queue, running the event once.
+	/// Used with non-concurrent drivers. Errors are logged and retried by the Event::run that queued the task
+	#[instrument(skip_all, fields(event = %self.event, interval = %self.event.interval))]
 	pub async fn run(&self, pool: AnyPool) -> Result<(), Box<dyn Error>> {
-		// Start a transaction to run the event on
 		let time_in_queue = (Local::now() - self.queued_at).to_std()?;
-		event!(Level::INFO, time_in_queue = format!("{:#?}", time_in_queue), "Running event");
-		let mut tx = pool.begin().await?;
-
+		log!(self.event, Level::INFO, time_in_queue = format!("{:#?}", time_in_queue), "Running event");
+		log!(self.event, Level::TRACE, "Runs {}", self.event.interval.describe());
 		// Run the event body
-		let mut i: usize = 0;
-		for stmt in &self.event.body {
-			let span = span!(Level::DEBUG, "Exec", stmt = i,  action = Event::action(stmt));
-			async {
-				let result = sqlx::query(stmt)
-					.execute(&mut *tx)
-					.await?;
-				event!(Level::DEBUG, "{} Rows affected", result.rows_affected());
-				Ok::<(), sqlx::Error>(())
-			}.instrument(span).await?;
-			i += 1;
-		}
-
-		tx.commit().await?;
-		event!(Level::INFO, "Done");
+		self.event.exec(&pool).await.map_err(|e| e as Box<dyn Error>)?;
+		log!(self.event, Level::INFO, "Done");
 		Ok(())
 	}
 }
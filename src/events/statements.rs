@@ -0,0 +1,215 @@
+use std::mem;
+
+use sqlparser::dialect::{Dialect, MySqlDialect};
+use sqlx::{any::AnyQueryResult, AnyConnection, Executor};
+
+use super::parsing::Span;
+
+#[cfg(test)]
+mod tests;
+
+/// Statements end with a semicolon, unless the delimiter is changed with a DELIMITER directive
+const DEFAULT_DELIMITER: &str = ";";
+
+/// An SQL statement of an event body
+#[derive(Debug)]
+pub(super) struct Statement {
+	pub text: String,
+	// Whether the statement ended with a custom delimiter (i.e BEGIN ... END),
+	// in which case it's run as-is instead of being prepared
+	pub compound: bool
+}
+
+impl Statement {
+	/// Run the statement on a connection
+	pub async fn execute(&self, conn: &mut AnyConnection) -> Result<AnyQueryResult, sqlx::Error> {
+		if self.compound {
+			conn.execute(sqlx::raw_sql(&self.text)).await
+		} else {
+			conn.execute(sqlx::query(&self.text)).await
+		}
+	}
+}
+
+/// Rules of the driver's SQL dialect that decide where literals and comments end
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Syntax {
+	backslash_escapes: bool, // Whether a backslash escapes the next character in quoted strings (MySQL)
+	dash_comment_space: bool // Whether -- only starts a comment when followed by whitespace (MySQL)
+}
+
+impl Syntax {
+	pub fn new(dialect: &dyn Dialect) -> Self {
+		Self {
+			backslash_escapes: dialect.supports_string_literal_backslash_escape(),
+			dash_comment_space: dialect.is::<MySqlDialect>()
+		}
+	}
+}
+
+/// What the splitter is reading
+#[derive(Debug, Clone, PartialEq)]
+enum State {
+	Code,
+	Quoted(char), // '...', "..." or `...`
+	LineComment, // -- ...
+	BlockComment, // /* ... */
+	DollarQuoted(String) // $tag$ ... $tag$ (Postgres)
+}
+
+/// Splits an event body into SQL statements on delimiters outside of literals and comments,
+/// keeping the text of each statement as written. Lines are pushed one at a time,
+/// so the delimiter and any open literal carry over to the following lines
+pub(super) struct Splitter {
+	syntax: Syntax,
+	state: State,
+	delimiter: String,
+	start: Option<Span>, // Position of the current statement, which starts at its first token after any comments
+	stmt: String,
+	statements: Vec<(Span, Statement)>
+}
+
+impl Splitter {
+	pub fn new(syntax: Syntax) -> Self {
+		Self {
+			syntax,
+			state: State::Code,
+			delimiter: DEFAULT_DELIMITER.into(),
+			start: None,
+			stmt: String::new(),
+			statements: Vec::new()
+		}
+	}
+
+	/// Read a line of the body
+	pub fn push(&mut self, line: &Span) {
+		let text = line.text.as_str();
+		if self.state == State::LineComment {
+			self.state = State::Code;
+		}
+		// DELIMITER directives are only read between statements
+		if self.state == State::Code && self.start.is_none() {
+			if let Some(delimiter) = delimiter(text) {
+				self.delimiter = delimiter.into();
+				return;
+			}
+		}
+		if self.start.is_some() {
+			self.stmt.push('\n');
+		}
+
+		let mut i = 0;
+		while i < text.len() {
+			let rest = &text[i..];
+			let c = rest.chars().next().unwrap();
+			let mut len = c.len_utf8();
+			match &self.state {
+				State::Code => {
+					if rest.starts_with(self.delimiter.as_str()) {
+						self.end();
+						i += self.delimiter.len();
+						continue;
+					}
+					// events.conf comments aren't SQL, so they're left out of the statement
+					if c == '#' && text[..i].chars().next_back().is_none_or(char::is_whitespace) {
+						break;
+					}
+					let dash_comment = rest.starts_with("--")
+						&& (!self.syntax.dash_comment_space || rest[2..].chars().next().is_none_or(char::is_whitespace));
+					if dash_comment {
+						self.state = State::LineComment;
+						len = 2;
+					} else if rest.starts_with("/*") {
+						self.state = State::BlockComment;
+						len = 2;
+					} else if !c.is_whitespace() {
+						if matches!(c, '\'' | '"' | '`') {
+							self.state = State::Quoted(c);
+						} else if let Some(tag) = dollar_tag(text, i) {
+							len = tag.len();
+							self.state = State::DollarQuoted(tag.to_string());
+						}
+						if self.start.is_none() {
+							self.start = Some(Span { line: line.line, offset: line.offset + i, text: String::new() });
+						}
+					}
+				},
+				State::Quoted(quote) => {
+					if c == '\\' && *quote != '`' && self.syntax.backslash_escapes { // Escaped character
+						len += rest[1..].chars().next().map_or(0, char::len_utf8);
+					} else if c == *quote {
+						if rest[1..].starts_with(*quote) { // Doubled quote
+							len = 2;
+						} else {
+							self.state = State::Code;
+						}
+					}
+				},
+				State::LineComment => {},
+				State::BlockComment => if rest.starts_with("*/") {
+					self.state = State::Code;
+					len = 2;
+				},
+				State::DollarQuoted(tag) => if rest.starts_with(tag.as_str()) {
+					len = tag.len();
+					self.state = State::Code;
+				}
+			}
+			if self.start.is_some() {
+				self.stmt.push_str(&rest[..len]);
+			}
+			i += len;
+		}
+	}
+
+	/// End the current statement, if it has started
+	fn end(&mut self) {
+		let stmt = mem::take(&mut self.stmt);
+		if let Some(mut span) = self.start.take() {
+			let stmt = stmt.trim_end();
+			span.text = stmt.lines().next().unwrap_or_default().trim_end().to_string();
+			self.statements.push((span, Statement {
+				text: stmt.to_string(),
+				compound: self.delimiter != DEFAULT_DELIMITER
+			}));
+		}
+	}
+
+	/// End the current statement, and any literal or comment left open, returning the statements read so far.
+	/// The delimiter is kept, as it applies until the end of the body
+	pub fn finish(&mut self) -> Vec<(Span, Statement)> {
+		self.end();
+		self.state = State::Code;
+		mem::take(&mut self.statements)
+	}
+
+	/// Whether the splitter is inside a literal or block comment, which continues on the next line
+	pub fn is_open(&self) -> bool {
+		!matches!(self.state, State::Code | State::LineComment)
+	}
+}
+
+/// New delimiter set by a `DELIMITER //` line, as in the mysql client
+fn delimiter(line: &str) -> Option<&str> {
+	let mut tokens = line.split_whitespace();
+	let directive = tokens.next()?;
+	match (tokens.next(), tokens.next()) {
+		(Some(delimiter), None) if directive.eq_ignore_ascii_case("DELIMITER") => Some(delimiter),
+		_ => None
+	}
+}
+
+/// Postgres dollar quote tag starting at `i` (i.e $$ or $body$), which can't follow an identifier
+/// and can't start with a digit, so it isn't mistaken for a parameter ($1)
+fn dollar_tag(text: &str, i: usize) -> Option<&str> {
+	let is_ident = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+	if !text[i..].starts_with('$') || text[..i].chars().next_back().is_some_and(is_ident) {
+		return None;
+	}
+	let end = text[i + 1..].find('$')? + i + 1;
+	let tag = &text[i + 1..end];
+	if tag.starts_with(|c: char| c.is_ascii_digit()) || !tag.chars().all(|c| c.is_alphanumeric() || c == '_') {
+		return None;
+	}
+	Some(&text[i..=end])
+}
@@ -0,0 +1,125 @@
+use sqlparser::dialect::{MySqlDialect, PostgreSqlDialect, SQLiteDialect};
+
+use super::{Splitter, Statement, Syntax};
+use crate::events::parsing::Span;
+
+/// Body lines of an event, as they're read from events.conf
+fn body(text: &str) -> Vec<Span> {
+	text.lines().enumerate().map(|(i, line)| Span { line: i + 1, offset: 0, text: line.to_string() }).collect()
+}
+
+/// Split a whole body into statements
+fn split(body: &[Span], syntax: Syntax) -> Vec<(Span, Statement)> {
+	let mut splitter = Splitter::new(syntax);
+	for line in body {
+		splitter.push(line);
+	}
+	splitter.finish()
+}
+
+/// Whether a body ends inside a literal or block comment
+fn is_open(body: &[Span], syntax: Syntax) -> bool {
+	let mut splitter = Splitter::new(syntax);
+	for line in body {
+		splitter.push(line);
+	}
+	splitter.is_open()
+}
+
+/// Text of each statement a body is split into
+fn statements(text: &str, syntax: Syntax) -> Vec<String> {
+	split(&body(text), syntax).into_iter().map(|(_, stmt)| stmt.text).collect()
+}
+
+fn mysql() -> Syntax {
+	Syntax::new(&MySqlDialect {})
+}
+
+fn postgres() -> Syntax {
+	Syntax::new(&PostgreSqlDialect {})
+}
+
+fn sqlite() -> Syntax {
+	Syntax::new(&SQLiteDialect {})
+}
+
+#[test]
+fn quotes() {
+	for syntax in [mysql(), postgres(), sqlite()] {
+		// Delimiters inside quotes, double quotes and backticks don't end statements
+		assert_eq!(statements("SELECT ';', \";\", `;`; SELECT 2;", syntax), ["SELECT ';', \";\", `;`", "SELECT 2"]);
+		// Doubled quotes are escaped quotes
+		assert_eq!(statements("SELECT 'it''s;'; SELECT 2;", syntax), ["SELECT 'it''s;'", "SELECT 2"]);
+		// Quotes continue across lines
+		assert_eq!(statements("SELECT 'a;\nb'; SELECT 2;", syntax), ["SELECT 'a;\nb'", "SELECT 2"]);
+	}
+
+	// Only MySQL escapes quotes with backslashes
+	assert_eq!(statements(r"SELECT 'a\';'; SELECT 2;", mysql()), [r"SELECT 'a\';'", "SELECT 2"]);
+	assert_eq!(statements(r"SELECT 'C:\'; SELECT 2;", postgres()), [r"SELECT 'C:\'", "SELECT 2"]);
+	assert_eq!(statements(r"SELECT 'C:\'; SELECT 2;", sqlite()), [r"SELECT 'C:\'", "SELECT 2"]);
+	assert_eq!(statements(r"SELECT `a\`; SELECT 2;", mysql()), [r"SELECT `a\`", "SELECT 2"]);
+}
+
+#[test]
+fn dollar_quotes() {
+	let function = "CREATE FUNCTION f() RETURNS int AS $$\n\tSELECT 1;\n$$ LANGUAGE sql; SELECT 2;";
+	assert_eq!(statements(function, postgres()), ["CREATE FUNCTION f() RETURNS int AS $$\n\tSELECT 1;\n$$ LANGUAGE sql", "SELECT 2"]);
+	assert_eq!(statements("SELECT $body$ $$; $body$; SELECT 2;", postgres()), ["SELECT $body$ $$; $body$", "SELECT 2"]);
+
+	// Parameters and identifiers containing $ aren't dollar quotes
+	assert_eq!(statements("SELECT $1; SELECT a$b$; SELECT 2;", postgres()), ["SELECT $1", "SELECT a$b$", "SELECT 2"]);
+}
+
+#[test]
+fn comments() {
+	for syntax in [mysql(), postgres(), sqlite()] {
+		// Statements start at their first token after any comments
+		assert_eq!(statements("SELECT 1; -- a; comment\nSELECT 2;", syntax), ["SELECT 1", "SELECT 2"]);
+		assert_eq!(statements("SELECT /* a; */ 1; /* b;\nc; */ SELECT 2;", syntax), ["SELECT /* a; */ 1", "SELECT 2"]);
+		// events.conf comments are left out of statements
+		assert_eq!(statements("SELECT 1; # a; comment\nSELECT '#;';", syntax), ["SELECT 1", "SELECT '#;'"]);
+	}
+
+	// MySQL only starts -- comments when they're followed by whitespace
+	assert_eq!(statements("SELECT 1--1; SELECT 2;", mysql()), ["SELECT 1--1", "SELECT 2"]);
+	assert_eq!(statements("SELECT 1--1; SELECT 2;", postgres()), ["SELECT 1--1; SELECT 2;"]);
+	assert_eq!(statements("SELECT 1 --\nSELECT 2;", mysql()), ["SELECT 1 --\nSELECT 2"]);
+}
+
+#[test]
+fn open() {
+	for (text, open) in [("SELECT 'a", true), ("SELECT /* a", true), ("SELECT $$ a", true), ("SELECT 1 -- 'a", false), ("SELECT 'a' /* b */", false)] {
+		assert_eq!(is_open(&body(text), postgres()), open, "{}", text);
+	}
+	assert!(is_open(&body(r"SELECT 'a\'"), mysql()));
+	assert!(!is_open(&body(r"SELECT 'a\'"), postgres()));
+}
+
+/// Each statement's text and whether it's compound
+fn compound(text: &str) -> Vec<(String, bool)> {
+	split(&body(text), mysql()).into_iter().map(|(_, stmt)| (stmt.text, stmt.compound)).collect()
+}
+
+#[test]
+fn delimiter() {
+	let body = "\
+DELIMITER //
+BEGIN NOT ATOMIC
+  IF (SELECT COUNT(*) FROM Orders WHERE Status = 'stale;') > 0 THEN
+    UPDATE Orders SET Status = 'closed' WHERE Status = 'stale';
+  END IF;
+END //
+DELIMITER ;
+DELETE FROM Carts WHERE Empty = 1;";
+	assert_eq!(compound(body), [
+		("BEGIN NOT ATOMIC\n  IF (SELECT COUNT(*) FROM Orders WHERE Status = 'stale;') > 0 THEN\n    UPDATE Orders SET Status = 'closed' WHERE Status = 'stale';\n  END IF;\nEND".into(), true),
+		("DELETE FROM Carts WHERE Empty = 1".into(), false)
+	]);
+
+	// The delimiter applies until the end of the body, and isn't read inside statements, literals or comments
+	assert_eq!(compound("DELIMITER $$\nSELECT 1; SELECT 2$$\nSELECT 3"), [("SELECT 1; SELECT 2".into(), true), ("SELECT 3".into(), true)]);
+	assert_eq!(compound("SELECT 1\nDELIMITER //\n;"), [("SELECT 1\nDELIMITER //".into(), false)]);
+	assert_eq!(compound("SELECT '\nDELIMITER //\n';"), [("SELECT '\nDELIMITER //\n'".into(), false)]);
+	assert_eq!(compound("/*\nDELIMITER //\n*/ SELECT 1;"), [("SELECT 1".into(), false)]);
+}
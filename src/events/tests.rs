@@ -0,0 +1,145 @@
+use std::time::{Duration, Instant};
+use sqlparser::dialect::MySqlDialect;
+use tokio::time;
+
+use super::{error::EventErrors, parse, EventQueue, Syntax, Validation};
+use crate::test_util::tree;
+
+#[tokio::test]
+async fn sql_files() {
+	let dir = tree("sql_files", &[
+		("events.conf", "\
+Close stale orders:
+0 * * * *
+	SELECT 1; SELECT 'a
+	@file literal.sql
+	';
+	DELIMITER //
+	@file sql/close_stale.sql
+	DELIMITER ;
+	@file sql/cleanup.sql
+	SELECT 2;
+"),
+		("sql/close_stale.sql", "BEGIN NOT ATOMIC\n  UPDATE Orders SET Status = 'closed' WHERE Status = 'stale';\nEND //\n"),
+		("sql/cleanup.sql", "-- Carts\nDELETE FROM Carts\nWHERE Empty = 1;\n\nDELETE FROM Sessions;\n")
+	]);
+	let events = parse(&dir.file("events.conf"), Syntax::new(&MySqlDialect {}), Validation::Parse(Box::new(MySqlDialect {})), None).await;
+
+	// @file lines inside literals are part of them, and an active DELIMITER applies to the file's statements
+	let events = events.unwrap();
+	let statements: Vec<(&str, bool)> = events[0].body.iter().map(|stmt| (stmt.text.as_str(), stmt.compound)).collect();
+	assert_eq!(statements, [
+		("SELECT 1", false),
+		("SELECT 'a\n\t@file literal.sql\n\t'", false),
+		("BEGIN NOT ATOMIC\n  UPDATE Orders SET Status = 'closed' WHERE Status = 'stale';\nEND", true),
+		("DELETE FROM Carts\nWHERE Empty = 1", false),
+		("DELETE FROM Sessions", false),
+		("SELECT 2", false)
+	]);
+}
+
+#[tokio::test]
+async fn sql_file_errors() {
+	let dir = tree("sql_file_errors", &[
+		("events.conf", "\
+Cleanup:
+0 * * * *
+	@file missing.sql
+	@file sql/cleanup.sql
+	SELEC 1;
+"),
+		("sql/cleanup.sql", "DELETE FROM Carts;\n\n-- Sessions\nDELETE FROM\n  WHERE Expired = 1;\n")
+	]);
+	let events = dir.file("events.conf");
+	let e = parse(&events, Syntax::default(), Validation::Parse(Box::new(MySqlDialect {})), None).await.err().unwrap();
+	let sql = dir.file("sql/cleanup.sql");
+
+	// Errors in SQL files point at the file, and the body's statements after them are still validated
+	let e = e.downcast::<EventErrors>().unwrap();
+	let locations: Vec<(&str, usize, usize, usize)> = e.errors.iter()
+		.map(|e| (e.location.path.as_str(), e.location.line, e.location.column, e.location.len)).collect();
+	assert_eq!(locations, [
+		(events.as_str(), 3, 8, 11),
+		(sql.as_str(), 4, 1, 11),
+		(events.as_str(), 5, 2, 7)
+	]);
+	assert!(e.errors[0].to_string().starts_with("error: Can't read missing.sql"));
+	// The embedded SQL parser's errors are only warnings
+	assert!(e.errors[1].to_string().starts_with("warning: Invalid SQL"));
+	assert!(e.to_string().ends_with("due to 1 previous error; 2 warnings emitted"), "{}", e);
+}
+
+#[test]
+fn retry_delays() {
+	let delays: Vec<u64> = (1..=8).map(|attempt| super::retry_delay(attempt).as_secs()).collect();
+	assert_eq!(delays, [1, 2, 4, 8, 16, 32, 60, 60]);
+	assert_eq!(super::retry_delay(u32::MAX).as_secs(), 60);
+}
+
+#[test]
+fn actions() {
+	assert_eq!(super::Event::action("UPDATE Users, Tokens SET Users.Email = Tokens.Email"), "UPDATE Users, Tokens");
+	assert_eq!(super::Event::action("INSERT INTO Logs (Message) VALUES ('a')"), "INSERT INTO Logs");
+	assert_eq!(super::Event::action("DELETE FROM Carts\nWHERE Empty = 1"), "DELETE FROM Carts");
+	// Single word statements are their own action
+	assert_eq!(super::Event::action("VACUUM"), "VACUUM");
+	assert_eq!(super::Event::action("ANALYZE"), "ANALYZE");
+	assert_eq!(super::Event::action("UPDATE"), "UPDATE");
+}
+
+#[tokio::test]
+async fn option_errors() {
+	let dir = tree("option_errors", &[("events.conf", "Cleanup [enabled=false nightly]:\n0 * * * *\n\tSELECT 1;\n")]);
+	let e = parse(&dir.file("events.conf"), Syntax::default(), Validation::None, None).await.err().unwrap();
+
+	// Trailing brackets are options, so words that aren't key=value are reported instead of being read as part of the name
+	let e = e.downcast::<EventErrors>().unwrap();
+	let locations: Vec<(usize, usize, usize)> = e.errors.iter().map(|e| (e.location.line, e.location.column, e.location.len)).collect();
+	assert_eq!(locations, [(1, 24, 7)]);
+	assert!(e.errors[0].to_string().starts_with("error: Invalid event option: nightly - expected key=value"));
+}
+
+#[tokio::test]
+async fn queued_retries() {
+	let dir = tree("queued_retries", &[("events.conf", "\
+Failing [retries=1]:
+0 * * * *
+	SELECT * FROM Missing;
+
+Working:
+0 * * * *
+	SELECT 1;
+")]);
+	let events = parse(&dir.file("events.conf"), Syntax::default(), Validation::None, None).await.unwrap();
+	sqlx::any::install_default_drivers();
+	let pool = sqlx::any::AnyPoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+
+	// Serial queue task, as my_timers runs with sqlite
+	let queue = EventQueue::new("sqlite", events.len());
+	let (tx, mut rx) = (queue.tx.unwrap(), queue.rx.unwrap());
+	let consumer = async {
+		while let Some(task) = rx.recv().await {
+			let result = task.run(pool.clone()).await.map_err(|e| e.to_string());
+			task.done.send(result).ok();
+		}
+	};
+	let start = Instant::now();
+	let runs = async {
+		let failing = events[0].run(pool.clone(), Some(tx.clone()));
+		let working = async {
+			time::sleep(Duration::from_millis(100)).await;
+			let result = events[1].run(pool.clone(), Some(tx.clone())).await.is_ok();
+			(result, start.elapsed())
+		};
+		let (failing, working) = tokio::join!(failing, working);
+		drop(tx);
+		(failing.is_err(), working)
+	};
+	let ((failed, (worked, elapsed)), _) = tokio::join!(runs, consumer);
+
+	// The failing event's retry delay is waited for outside of the queue, so other events aren't held up
+	assert!(failed);
+	assert!(worked);
+	assert!(elapsed < Duration::from_millis(900), "{:?}", elapsed);
+	assert!(start.elapsed() >= Duration::from_secs(1));
+}
@@ -1,8 +1,8 @@
-use std::{error::Error, time::Duration};
-use chrono::{Timelike, Local};
-use tokio::{time, task::JoinSet, signal as tokio_signal}; 
+use std::{error::Error, process::exit};
+use tokio::{time, task::JoinSet, signal as tokio_signal, sync::mpsc}; 
 use tracing::{event, Level, span, Instrument, instrument};
 use sqlx::AnyPool;
+use chrono::{DateTime, Local, Utc};
 
 mod config;
 mod db;
@@ -11,18 +11,47 @@ mod events;
 mod cron;
 mod args;
 mod signal;
+mod scheduler;
+mod state;
+mod blackout;
+mod check;
+#[cfg(test)]
+mod test_util;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
 	// Parse CLI args
 	let args = args::args();
 
+	// List events without connecting to the database.
+	// config.json isn't read, so statements are split without the driver's dialect-specific rules, and blackouts aren't checked
+	if args.command == args::Command::List {
+		let events = events::parse(&args.events_path, events::Syntax::default(), events::Validation::None, None).await.map_err(|e| {
+			eprintln!("{}", e);
+			format!("Failed to parse {}", &args.events_path)
+		})?;
+		list(&events);
+		return Ok(());
+	}
+
+	// Validate config and events without connecting to the database
+	if args.command == args::Command::Check {
+		exit(check::check(&args).await);
+	}
+
 	// Parse config
-	let config = config::parse(&args.config_path).or_else(|err| {
+	let config = config::parse(&args.config_path).inspect_err(|_| {
 		eprintln!("Failed to parse {}:", &args.config_path);
-		Err(err)
 	})?;
 
+	let blackouts = blackout::Blackouts::load(&config.blackouts).inspect_err(|_| {
+		eprintln!("Failed to load blackouts from {}:", &args.config_path);
+	})?;
+
+	// Load persisted scheduler state
+	let state = state::load(&args.state_path).inspect_err(|_| {
+		eprintln!("Failed to load {}:", &args.state_path);
+	})?;
 
 	// Initialize logging destinations
 	let _guards = config.log.init(args.verbose);
@@ -47,24 +76,33 @@ async fn main() -> Result<(), Box<dyn Error>> {
 	// tokio::select! must be used to catch signals for all future awaits
 	// on the main thread
 	let ctrl_c = tokio_signal::ctrl_c();
-	let mut sigterm_channel = signal::new(signal::SignalKind::SIGTERM).or_else(|err| {
+	let mut sigterm_channel = signal::new(signal::SignalKind::SIGTERM).inspect_err(|_| {
 		eprintln!("Failed to create SIGTERM channel");
-		Err(err)
 	})?;
 	let sigterm = sigterm_channel.recv();
 	tokio::pin!(ctrl_c);
 	tokio::pin!(sigterm);
 
 	// Read events from config
+	let syntax = events::Syntax::new(config.db.dialect()?.as_ref());
+	let blackout_names = blackouts.names();
 	let events = tokio::select! {
-		evts = events::parse(&args.events_path, pool.clone()) => evts.or_else(|err| {
-			eprintln!("Failed to parse {}:", &args.events_path);
-			Err(err)
+		evts = events::parse(&args.events_path, syntax, events::Validation::Prepare(pool.clone()), Some(&blackout_names)) => evts.map_err(|e| {
+			eprintln!("{}", e);
+			format!("Failed to parse {}", &args.events_path)
 		})?,
 		Ok(_) = &mut ctrl_c => return shutdown(None, pool).await,
 		Some(_) = &mut sigterm => return shutdown(None, pool).await
 	};
 
+	// Disabled events are validated, but never run
+	let events: Vec<_> = events.into_iter().filter(|evt| {
+		if !evt.options.enabled {
+			event!(Level::INFO, event = %evt, "Event disabled");
+		}
+		evt.options.enabled
+	}).collect();
+
 	// Initialize task joinset
 	let mut event_threads = JoinSet::<()>::new();
 
@@ -76,16 +114,19 @@ async fn main() -> Result<(), Box<dyn Error>> {
 		let pool = pool.clone();
 		event_threads.spawn(async move {
 			while let Some(evt) = rx.recv().await {
-				// Error logging is handled in the event's tracing span
-				evt.run(pool.clone()).await.ok();
+				// Errors are logged by the Event::run that queued the event, which retries it if needed
+				let result = evt.run(pool.clone()).await.map_err(|e| e.to_string());
+				evt.done.send(result).ok();
 			}
 		});
 	}
 
+	let mut scheduler = scheduler::Scheduler::new(&events, state, blackouts);
+
 	// Immediately run @startup events
 	event!(Level::INFO, "Running @startup events");
 	for evt in &events {
-		if evt.interval.startup {
+		if evt.interval.startup() && !scheduler.blacked_out(evt, &Utc::now()) {
 			let pool = pool.clone();
 			let tx = event_queue.tx.clone();
 			// These events are pinned and will NEVER be mutated once the event loop starts,
@@ -97,65 +138,72 @@ async fn main() -> Result<(), Box<dyn Error>> {
 		}
 	}
 
-	// Wait until next minute at 00 seconds to start event loop
-	let mut interval = {
-		let mut to_minute = time::interval(to_next_minute());
-		to_minute.tick().await;
-		event!(Level::INFO, "Starting event loop in {:?}", to_minute.period());
-		tokio::select! {
-			_ = to_minute.tick() => {
-				// Start minute interval ticker
-				time::interval(Duration::from_secs(60))
-			},
-			Ok(_) = &mut ctrl_c => return shutdown(Some(event_threads), pool).await,
-			Some(_) = &mut sigterm => return shutdown(Some(event_threads), pool).await
-		}
-	};
-	event!(parent: None, Level::INFO, "Starting event loop");
+	// Successful scheduled runs are sent back to the event loop to be recorded: (event index, scheduled time)
+	let (ran_tx, mut ran_rx) = mpsc::unbounded_channel::<(usize, DateTime<Utc>)>();
+
+	// Make up runs missed while my_timers wasn't running
+	for (i, n, t) in scheduler.catch_up(&events) {
+		let pool = pool.clone();
+		let tx = event_queue.tx.clone();
+		let ran_tx = ran_tx.clone();
+		let evt = unsafe { (&*events[i] as *const events::Event).as_ref() }.unwrap();
+		event_threads.spawn(async move {
+			for _ in 0..n {
+				if evt.run(pool.clone(), tx.clone()).await.is_ok() {
+					ran_tx.send((i, t)).ok();
+				}
+			}
+		});
+	}
 
 	// Event loop
+	event!(parent: None, Level::INFO, "Starting event loop");
 	loop {
-		// Wait for the next minute, breaking the loop if a signal is caught
+		// Wait for the next event to be due, breaking the loop if a signal is caught
 		tokio::select! {
-			_ = interval.tick() => {},
+			_ = time::sleep(scheduler.until_next()) => {},
+			Some((i, t)) = ran_rx.recv() => scheduler.record(&events[i], t),
 			Ok(_) = &mut ctrl_c => return shutdown(Some(event_threads), pool).await,
 			Some(_) = &mut sigterm => return shutdown(Some(event_threads), pool).await
 		}
-		// Iterate through each event, run the ones that match
-		let now = Local::now();
-		for evt in &events {
-			if evt.interval.match_time(&now) {
-				let pool = pool.clone();
-				let tx = event_queue.tx.clone();
-				let evt = unsafe { (&**evt as *const events::Event).as_ref() }.unwrap();
-				event_threads.spawn(async move {
-					evt.run(pool, tx).await.ok();
-				});
-			}
+		// Run every event that's due
+		for (i, t) in scheduler.due(&events) {
+			let pool = pool.clone();
+			let tx = event_queue.tx.clone();
+			let ran_tx = ran_tx.clone();
+			let evt = unsafe { (&*events[i] as *const events::Event).as_ref() }.unwrap();
+			event_threads.spawn(async move {
+				if evt.run(pool, tx).await.is_ok() {
+					ran_tx.send((i, t)).ok();
+				}
+			});
 		}
+		scheduler.save_state().await;
 	}
 }
 
-/// Duration until next minute
-fn to_next_minute() -> Duration {
-	// Ceil to nearest ms + 1
-	let mut next = chrono::Utc::now();
-	next = if next.minute() == 59 {
-		next.with_hour(next.hour()+1).unwrap()
-		.with_minute(0).unwrap()
-	} else {
-		next.with_minute(next.minute()+1).unwrap()
-	};
-	next = next.with_second(0).unwrap()
-		.with_nanosecond(0).unwrap();
-
-	let mut now = chrono::Utc::now(); // Get duration against current time
-	// Round up to next ms
-	now = now.with_nanosecond(1_000_000 * (now.nanosecond() / 1_000_000) + 2_000_000).unwrap();
-	(next - now).to_std().unwrap()
+/// Print each event's schedule and upcoming runs
+fn list(events: &[std::pin::Pin<Box<events::Event>>]) {
+	let now = Local::now();
+	for evt in events {
+		println!("{}\n\t{}\n\t{}\n\tfrom {}", evt, evt.interval, evt.interval.describe(), evt.file);
+		if !evt.blackouts.is_empty() {
+			println!("\tblackouts: {}", evt.blackouts.join(", "));
+		}
+		let options = evt.options.to_string();
+		if !options.is_empty() {
+			println!("\toptions: {}", options);
+		}
+		let upcoming: Vec<String> = evt.interval.upcoming(&now).take(3)
+			.map(|t| t.with_timezone(&Local).to_rfc3339()).collect();
+		if upcoming.is_empty() {
+			println!("\tno upcoming runs");
+		} else {
+			println!("\tnext runs: {}", upcoming.join(", "));
+		}
+	}
 }
 
-
 /// Safely shutdown the main thread
 #[instrument(name = "Shutting down", skip_all, err)]
 async fn shutdown(event_threads: Option<JoinSet<()>>, pool: AnyPool) -> Result<(), Box<dyn Error>> {
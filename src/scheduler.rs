@@ -0,0 +1,214 @@
+use std::{cmp::Reverse, collections::BinaryHeap, pin::Pin, time::{Duration, Instant}};
+use chrono::{DateTime, DurationRound, Local, Utc};
+use tracing::{event, Level};
+
+use crate::blackout::Blackouts;
+use crate::cron::{CatchUp, Schedule};
+use crate::events::Event;
+use crate::state::State;
+
+#[cfg(test)]
+mod tests;
+
+/// Longest time the scheduler sleeps before re-checking the wall clock.
+/// Monotonic sleeps don't advance while the host is suspended, so this bounds how late a run can be after resuming.
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+/// Largest drift between the monotonic and wall clocks across one sleep before it's logged as a clock jump
+const CLOCK_JUMP_THRESHOLD: Duration = Duration::from_secs(5);
+/// Most missed runs counted for logging, so a long suspend doesn't enumerate every run of a frequent event
+const MAX_MISSED_COUNT: usize = 1000;
+
+/// Priority queue of the next run time of each event
+pub struct Scheduler {
+	queue: BinaryHeap<Reverse<(DateTime<Utc>, usize)>>, // (next run, event index)
+	last_wake: (Instant, DateTime<Utc>), // Monotonic and wall clock time of the last wake
+	state: State,
+	unsaved: bool, // Whether the state has changed since it was last saved
+	blackouts: Blackouts
+}
+
+impl Scheduler {
+	pub fn new(events: &[Pin<Box<Event>>], state: State, blackouts: Blackouts) -> Self {
+		let now = Utc::now();
+		let mut queue = BinaryHeap::with_capacity(events.len());
+		for (i, evt) in events.iter().enumerate() {
+			// One-shot events are done once a successful run has been recorded,
+			// and run immediately if their time passed while my_timers wasn't running
+			if let Schedule::At(at) = &evt.interval {
+				if state.last_run(&evt.label).is_some_and(|t| t >= at.time) {
+					event!(Level::INFO, event = %evt, "Expired, ran once at {}", at.time.with_timezone(&Local));
+					continue;
+				}
+				if at.time <= now {
+					event!(Level::INFO, event = %evt, "Running one-shot event missed at {}", at.time.with_timezone(&Local));
+					queue.push(Reverse((at.time, i)));
+					continue;
+				}
+			}
+			if let Some(next) = evt.interval.next_after(&now) {
+				event!(Level::DEBUG, event = %evt, "Next run at {}", next.with_timezone(&Local));
+				queue.push(Reverse((next, i)));
+			}
+		}
+		Self {
+			queue,
+			last_wake: (Instant::now(), now),
+			state,
+			unsaved: false,
+			blackouts
+		}
+	}
+
+	/// Number of runs to make up for each event that missed runs while my_timers wasn't running,
+	/// according to the event's catch-up policy and its persisted last run: (event index, runs, time to record once they succeed).
+	/// One-shot events are queued by Scheduler::new instead.
+	pub fn catch_up(&self, events: &[Pin<Box<Event>>]) -> Vec<(usize, u32, DateTime<Utc>)> {
+		let now = Utc::now();
+		let mut runs = Vec::new();
+		for (i, evt) in events.iter().enumerate() {
+			let limit = match evt.interval.catchup() {
+				CatchUp::None => continue,
+				CatchUp::Once => 1,
+				CatchUp::All(limit) => limit
+			};
+			let Some(last_run) = self.state.last_run(&evt.label) else {
+				continue;
+			};
+			// Only count one run past the limit, which is enough to tell whether any are skipped
+			let missed = count_missed(&evt.interval, &last_run, &now, limit as usize + 1);
+			if missed == 0 {
+				continue;
+			}
+			if self.blacked_out(evt, &now) {
+				continue;
+			}
+			let n = limit.min(missed as u32);
+			if missed > n as usize {
+				event!(Level::INFO, event = %evt, "Catching up {} run(s) missed since {}, skipping the rest", n, last_run.with_timezone(&Local));
+			} else {
+				event!(Level::INFO, event = %evt, "Catching up {} run(s) missed since {}", n, last_run.with_timezone(&Local));
+			}
+			runs.push((i, n, now));
+		}
+		runs
+	}
+
+	/// Time to sleep until the next event is due
+	pub fn until_next(&self) -> Duration {
+		match self.queue.peek() {
+			Some(Reverse((next, _))) => (*next - Utc::now()).to_std()
+				.unwrap_or(Duration::ZERO)
+				.min(MAX_SLEEP),
+			None => MAX_SLEEP
+		}
+	}
+
+	/// Pop every event that is due according to the wall clock and schedule its next run: (event index, scheduled time).
+	/// Each scheduled run is returned at most once; runs that were missed entirely
+	/// (i.e while the host was suspended) are collapsed into one and logged.
+	/// Runs suppressed by a blackout aren't returned, one-shot events are retried every minute until the blackout ends.
+	pub fn due(&mut self, events: &[Pin<Box<Event>>]) -> Vec<(usize, DateTime<Utc>)> {
+		self.due_at(events, Utc::now())
+	}
+
+	/// Scheduler::due, with the wall clock reading `now`
+	fn due_at(&mut self, events: &[Pin<Box<Event>>], now: DateTime<Utc>) -> Vec<(usize, DateTime<Utc>)> {
+		self.check_clock(now);
+
+		let mut due = Vec::new();
+		while let Some(Reverse((next, i))) = self.queue.peek().copied() {
+			if next > now {
+				break;
+			}
+			self.queue.pop();
+			let evt = &events[i];
+			let suppressed = self.blacked_out(evt, &now);
+			if !suppressed {
+				due.push((i, next));
+			}
+
+			// One-shot events are never scheduled again once they run
+			if let Schedule::At(_) = evt.interval {
+				if suppressed {
+					let retry = now.duration_trunc(chrono::Duration::minutes(1)).unwrap_or(now) + chrono::Duration::minutes(1);
+					self.queue.push(Reverse((retry, i)));
+				}
+				continue;
+			}
+
+			// Schedule the run after this one, skipping any that are already in the past
+			match count_missed(&evt.interval, &next, &now, MAX_MISSED_COUNT + 1) {
+				0 => {},
+				missed if missed > MAX_MISSED_COUNT => {
+					event!(Level::WARN, event = %evt, "Missed more than {} runs since {}", MAX_MISSED_COUNT, next.with_timezone(&Local));
+				},
+				missed => event!(Level::WARN, event = %evt, "Missed {} run(s) since {}", missed, next.with_timezone(&Local))
+			}
+			if let Some(following) = evt.interval.next_after(&now) {
+				self.queue.push(Reverse((following, i)));
+			}
+		}
+		due
+	}
+
+	/// Record a successful run scheduled at `t`, for events that catch up on missed runs or only run once.
+	/// Runs are only recorded once they succeed, so failed runs are caught up on (or retried for one-shot events) after a restart.
+	pub fn record(&mut self, evt: &Event, t: DateTime<Utc>) {
+		// Runs can finish out of order, the latest one is kept
+		if !evt.interval.records_runs() || self.state.last_run(&evt.label).is_some_and(|last| last >= t) {
+			return;
+		}
+		self.state.set_last_run(&evt.label, t);
+		self.unsaved = true;
+		if let Schedule::At(_) = evt.interval {
+			event!(Level::INFO, event = %evt, "One-shot event ran, now expired");
+		}
+	}
+
+	/// Whether an event's run is suppressed by a blackout, logging the reason if it is
+	pub fn blacked_out(&self, evt: &Event, now: &DateTime<Utc>) -> bool {
+		match self.blackouts.check(evt, now) {
+			Some(reason) => {
+				event!(Level::INFO, event = %evt, "Run suppressed by blackout {}", reason);
+				true
+			},
+			None => false
+		}
+	}
+
+	/// Save the state file if runs have been recorded since it was last saved
+	pub async fn save_state(&mut self) {
+		if !self.unsaved {
+			return;
+		}
+		match self.state.save().await {
+			Ok(_) => self.unsaved = false,
+			Err(e) => event!(Level::ERROR, "Failed to save scheduler state: {}", e)
+		}
+	}
+
+	/// Compare elapsed monotonic and wall clock time since the last wake to detect clock jumps,
+	/// returning how far the wall clock jumped (negative if it went backward).
+	/// Queued runs keep their scheduled times, so runs missed by a forward jump are collapsed into one by Scheduler::due,
+	/// and runs aren't repeated when the clock goes backward
+	fn check_clock(&mut self, now: DateTime<Utc>) -> Option<chrono::Duration> {
+		let (last_instant, last_time) = self.last_wake;
+		self.last_wake = (Instant::now(), now);
+		let monotonic = chrono::Duration::from_std(last_instant.elapsed()).unwrap_or_else(|_| chrono::Duration::max_value());
+		let jump = (now - last_time) - monotonic;
+		let threshold = chrono::Duration::from_std(CLOCK_JUMP_THRESHOLD).unwrap();
+		if jump > threshold {
+			event!(Level::WARN, "Clock jumped forward by {}", jump);
+		} else if jump < -threshold {
+			event!(Level::WARN, "Clock jumped backward by {}", -jump);
+		} else {
+			return None;
+		}
+		Some(jump)
+	}
+}
+
+/// Number of runs scheduled in (since, now], counting at most `limit`
+fn count_missed(schedule: &Schedule, since: &DateTime<Utc>, now: &DateTime<Utc>, limit: usize) -> usize {
+	schedule.upcoming(since).take_while(|t| t <= now).take(limit).count()
+}
@@ -0,0 +1,216 @@
+use std::{cmp::Reverse, pin::Pin, time::Instant};
+use chrono::{DateTime, Duration, DurationRound, Utc};
+
+use crate::blackout::Blackouts;
+use crate::events::{self, Event};
+use crate::state;
+use crate::test_util::{tree, Tree};
+use super::{Scheduler, MAX_SLEEP};
+
+/// Write an events file containing a single event, in a temporary directory for the test's state file
+async fn parse(name: &str, interval: &str) -> (Tree, Vec<Pin<Box<Event>>>) {
+	let dir = tree(name, &[("events.conf", &format!("{}:\n{}\n\tSELECT 1;\n", name, interval))]);
+	let events = events::parse(&dir.file("events.conf"), events::Syntax::default(), events::Validation::None, None).await.unwrap();
+	(dir, events)
+}
+
+/// Restart the scheduler from the state file, as my_timers does when it starts
+fn reload(events: &[Pin<Box<Event>>], path: &str) -> Scheduler {
+	Scheduler::new(events, state::load(path).unwrap(), Blackouts::load(&Default::default()).unwrap())
+}
+
+/// The current time in whole seconds, as @at times are parsed
+fn now() -> DateTime<Utc> {
+	Utc::now().duration_trunc(Duration::seconds(1)).unwrap()
+}
+
+fn at(t: DateTime<Utc>) -> String {
+	format!("@at {}", t.to_rfc3339())
+}
+
+#[tokio::test]
+async fn one_shot_expires() {
+	let time = now() - Duration::hours(1);
+	let (dir, events) = parse("one_shot_expires", &at(time)).await;
+	let path = dir.file("state.json");
+
+	// Missed one-shot events are queued to run immediately, and only once
+	let mut scheduler = reload(&events, &path);
+	assert!(scheduler.catch_up(&events).is_empty());
+	assert_eq!(scheduler.due(&events), vec![(0, time)]);
+	assert!(scheduler.due(&events).is_empty());
+
+	// Once the run is recorded, it's persisted and the event expires on later starts
+	scheduler.record(&events[0], time);
+	scheduler.save_state().await;
+	let mut scheduler = reload(&events, &path);
+	assert_eq!(scheduler.until_next(), MAX_SLEEP);
+	assert!(scheduler.due(&events).is_empty());
+}
+
+#[tokio::test]
+async fn one_shot_failed() {
+	let time = now() - Duration::hours(1);
+	let (dir, events) = parse("one_shot_failed", &at(time)).await;
+	let path = dir.file("state.json");
+
+	// Runs that didn't succeed aren't recorded, so the event runs again after a restart
+	let mut scheduler = reload(&events, &path);
+	assert_eq!(scheduler.due(&events), vec![(0, time)]);
+	scheduler.save_state().await;
+	let mut scheduler = reload(&events, &path);
+	assert_eq!(scheduler.due(&events), vec![(0, time)]);
+}
+
+#[tokio::test]
+async fn one_shot_upcoming() {
+	let (dir, events) = parse("one_shot_upcoming", &at(now() + Duration::hours(1))).await;
+	let path = dir.file("state.json");
+
+	let mut scheduler = reload(&events, &path);
+	assert!(scheduler.catch_up(&events).is_empty());
+	assert!(scheduler.due(&events).is_empty());
+	assert_eq!(scheduler.until_next(), MAX_SLEEP);
+
+	// Recording an older run doesn't mark the event done
+	scheduler.record(&events[0], now() - Duration::days(1));
+	scheduler.save_state().await;
+	let mut scheduler = reload(&events, &path);
+	assert!(scheduler.queue.peek().is_some());
+	assert!(scheduler.due(&events).is_empty());
+}
+
+#[tokio::test]
+async fn catch_up_persists() {
+	let (dir, events) = parse("catch_up_persists", "@every 1m @catchup(all,5)").await;
+	let path = dir.file("state.json");
+
+	// Runs aren't caught up on without a recorded last run
+	let mut scheduler = reload(&events, &path);
+	assert!(scheduler.catch_up(&events).is_empty());
+	let last_run = now() - Duration::hours(1);
+	scheduler.record(&events[0], last_run);
+	scheduler.save_state().await;
+
+	// Missed runs are capped by the catch-up policy
+	let mut scheduler = reload(&events, &path);
+	let runs = scheduler.catch_up(&events);
+	assert_eq!(runs.len(), 1);
+	let (i, n, t) = runs[0];
+	assert_eq!((i, n), (0, 5));
+	assert!(t > last_run);
+
+	// Catching up is done once its runs are recorded
+	scheduler.record(&events[0], t);
+	scheduler.save_state().await;
+	let scheduler = reload(&events, &path);
+	assert!(scheduler.catch_up(&events).is_empty());
+}
+
+#[tokio::test]
+async fn one_shot_blacked_out() {
+	let time = now() - Duration::hours(1);
+	let (dir, events) = parse("one_shot_blacked_out", &at(time)).await;
+	let path = dir.file("state.json");
+	let blackouts = serde_json::from_str(r#"{"always": {"global": true, "windows": ["* * * * *"]}}"#).unwrap();
+
+	// Suppressed one-shot events aren't run or recorded, and are retried at the start of the next minute
+	let mut scheduler = Scheduler::new(&events, state::load(&path).unwrap(), Blackouts::load(&blackouts).unwrap());
+	assert!(scheduler.due(&events).is_empty());
+	let Some(Reverse((retry, 0))) = scheduler.queue.peek().copied() else {
+		panic!("one-shot event wasn't retried");
+	};
+	assert!(retry > now() && retry <= now() + Duration::minutes(1), "{}", retry);
+	assert_eq!(scheduler.state.last_run("one_shot_blacked_out"), None);
+
+	// Once the blackout ends, the retry runs the event
+	scheduler.blackouts = Blackouts::load(&Default::default()).unwrap();
+	let retry = now();
+	scheduler.queue = [Reverse((retry, 0))].into();
+	assert_eq!(scheduler.due(&events), vec![(0, retry)]);
+}
+
+/// Next queued run of the only event
+fn queued(scheduler: &Scheduler) -> DateTime<Utc> {
+	let Reverse((next, _)) = scheduler.queue.peek().unwrap();
+	*next
+}
+
+/// Runs missed across a suspend are collapsed into one, then the event is back on schedule
+async fn missed_runs(name: &str, interval: &str) {
+	let (dir, events) = parse(name, interval).await;
+	let mut scheduler = reload(&events, &dir.file("state.json"));
+	let first = queued(&scheduler);
+
+	// The host resumes an hour later: the wall clock moved, the monotonic clock didn't
+	let resumed = first + Duration::hours(1);
+	scheduler.last_wake = (Instant::now(), first - Duration::minutes(1));
+	assert_eq!(scheduler.due_at(&events, resumed), vec![(0, first)]);
+	assert_eq!(scheduler.last_wake.1, resumed);
+	let next = queued(&scheduler);
+	assert!(next > resumed && next <= resumed + Duration::minutes(1), "{}", next);
+	assert_eq!(scheduler.queue.len(), 1);
+
+	// No run is returned twice
+	assert!(scheduler.due_at(&events, resumed).is_empty());
+	assert_eq!(scheduler.due_at(&events, next), vec![(0, next)]);
+	assert!(scheduler.due_at(&events, next).is_empty());
+}
+
+#[tokio::test]
+async fn missed_cron_runs() {
+	missed_runs("missed_cron_runs", "* * * * *").await;
+}
+
+#[tokio::test]
+async fn missed_every_runs() {
+	missed_runs("missed_every_runs", "@every 1m").await;
+}
+
+/// Runs aren't repeated when the clock goes backward, the event waits for its next scheduled run
+async fn backward_jump(name: &str, interval: &str) {
+	let (dir, events) = parse(name, interval).await;
+	let mut scheduler = reload(&events, &dir.file("state.json"));
+	let first = queued(&scheduler);
+	scheduler.last_wake = (Instant::now(), first);
+	assert_eq!(scheduler.due_at(&events, first), vec![(0, first)]);
+	let next = queued(&scheduler);
+
+	// Back by half an hour, then through the time of the run that already happened
+	let back = first - Duration::minutes(30);
+	assert!(scheduler.due_at(&events, back).is_empty());
+	assert_eq!(scheduler.last_wake.1, back);
+	assert_eq!(queued(&scheduler), next);
+	scheduler.last_wake = (Instant::now(), first);
+	assert!(scheduler.due_at(&events, first).is_empty());
+	assert_eq!(scheduler.due_at(&events, next), vec![(0, next)]);
+	assert_eq!(scheduler.queue.len(), 1);
+}
+
+#[tokio::test]
+async fn backward_cron_jump() {
+	backward_jump("backward_cron_jump", "* * * * *").await;
+}
+
+#[tokio::test]
+async fn backward_every_jump() {
+	backward_jump("backward_every_jump", "@every 1m").await;
+}
+
+#[tokio::test]
+async fn clock_jumps() {
+	let (dir, events) = parse("clock_jumps", "* * * * *").await;
+	let mut scheduler = reload(&events, &dir.file("state.json"));
+	let t = now();
+	scheduler.last_wake = (Instant::now(), t);
+
+	// Drift within the threshold isn't a jump
+	assert_eq!(scheduler.check_clock(t + Duration::seconds(1)), None);
+
+	// Jumps are measured against the monotonic clock since the last wake
+	let jump = scheduler.check_clock(t + Duration::hours(2)).unwrap();
+	assert!(jump > Duration::minutes(119) && jump <= Duration::hours(2), "{}", jump);
+	let jump = scheduler.check_clock(t).unwrap();
+	assert!(jump < -Duration::minutes(119) && jump >= -Duration::hours(2) - Duration::seconds(1), "{}", jump);
+	assert_eq!(scheduler.last_wake.1, t);
+}
@@ -19,10 +19,12 @@ impl SignalChannel for unix_signal::Signal {
 }
 
 /// A Future that never resolves
+#[cfg_attr(unix, allow(dead_code))]
 pub struct Never<T> {
 	output_type: PhantomData<T>
 }
 
+#[cfg_attr(unix, allow(dead_code))]
 impl<T> Never<T> {
 	fn new() -> Self {
 		Self {
@@ -40,6 +42,7 @@ impl<T> Future for Never<T> {
 
 /// A SinalChannel that never fires.
 /// Used as a placeholder on platforms where certain signals aren't supported.
+#[cfg_attr(unix, allow(dead_code))]
 pub struct SigNever;
 
 #[async_trait]
@@ -50,6 +53,7 @@ impl SignalChannel for SigNever {
 }
 
 /// Unix signal types accepted by new()
+#[allow(clippy::upper_case_acronyms)]
 pub enum SignalKind {
 	SIGTERM
 }
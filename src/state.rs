@@ -0,0 +1,43 @@
+use std::{collections::HashMap, error::Error, fs, io, path::Path};
+use tokio::fs as tokio_fs;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Scheduler state persisted across restarts
+#[derive(Serialize, Deserialize, Default)]
+pub struct State {
+	// Scheduled time of each event's most recent run, keyed by event label
+	#[serde(default)]
+	last_run: HashMap<String, DateTime<Utc>>,
+
+	#[serde(skip)]
+	path: String
+}
+
+impl State {
+	pub fn last_run(&self, label: &str) -> Option<DateTime<Utc>> {
+		self.last_run.get(label).copied()
+	}
+
+	pub fn set_last_run(&mut self, label: &str, t: DateTime<Utc>) {
+		self.last_run.insert(label.to_string(), t);
+	}
+
+	/// Write the state file, replacing it atomically
+	pub async fn save(&self) -> io::Result<()> {
+		let tmp_path = format!("{}.tmp", &self.path);
+		tokio_fs::write(&tmp_path, serde_json::to_vec_pretty(self)?).await?;
+		tokio_fs::rename(&tmp_path, &self.path).await
+	}
+}
+
+/// Load the state file, starting from an empty state if it doesn't exist yet
+pub fn load(path: &str) -> Result<State, Box<dyn Error>> {
+	let mut state: State = if Path::new(path).exists() {
+		serde_json::from_slice(&fs::read(path)?)?
+	} else {
+		State::default()
+	};
+	state.path = path.to_string();
+	Ok(state)
+}
@@ -0,0 +1,38 @@
+use std::{fs, ops::Deref, path::{Path, PathBuf}, process};
+
+/// A temporary directory, unique to this process and test, removed when it's dropped
+pub struct Tree(PathBuf);
+
+impl Tree {
+	/// Path of a file in the directory, as a string
+	pub fn file(&self, path: &str) -> String {
+		self.0.join(path).to_string_lossy().into_owned()
+	}
+}
+
+impl Deref for Tree {
+	type Target = Path;
+
+	fn deref(&self) -> &Path {
+		&self.0
+	}
+}
+
+impl Drop for Tree {
+	fn drop(&mut self) {
+		fs::remove_dir_all(&self.0).ok();
+	}
+}
+
+/// Write files to a temporary directory, replacing any left from a previous run
+pub fn tree(name: &str, files: &[(&str, &str)]) -> Tree {
+	let dir = std::env::temp_dir().join(format!("my_timers-{}-{}", process::id(), name));
+	fs::remove_dir_all(&dir).ok();
+	fs::create_dir_all(&dir).unwrap();
+	for (path, contents) in files {
+		let path = dir.join(path);
+		fs::create_dir_all(path.parent().unwrap()).unwrap();
+		fs::write(path, contents).unwrap();
+	}
+	Tree(dir)
+}